@@ -0,0 +1,163 @@
+//! Generation of the `is_*`, `as_*`, `as_*_mut` and `into_*` accessors of the variants.
+
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Fields, Generics, Variant, Visibility};
+
+use crate::{deprecated_attrs, forwarded_attrs};
+
+/// Generates the accessors for every variant of the enum.
+///
+/// All variants get `is_*`, variants with unnamed fields also get `as_*`, `as_*_mut` and `into_*`.
+/// Variants with multiple unnamed fields return tuples of them.
+pub(crate) fn process_accessors<'a>(
+    variants: impl Iterator<Item = &'a Variant>,
+    enum_name: &Ident,
+    vis: &Visibility,
+    generics: &Generics,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let methods = variants.map(|variant| variant_accessors(variant, vis));
+
+    quote! {
+        #[allow(dead_code)]
+        impl #impl_generics #enum_name #ty_generics #where_clause {
+            #(#methods)*
+        }
+    }
+}
+
+/// Generates the accessors of a single variant.
+fn variant_accessors(variant: &Variant, vis: &Visibility) -> TokenStream {
+    let variant_name = &variant.ident;
+    let snake = snake_case(&variant_name.to_string());
+    let is = format_ident!("is_{}", snake);
+    let is_doc = format!("Returns true if this is [`Self::{}`].", variant_name);
+    // Accessors of a deprecated variant are deprecated along with it.
+    let forwarded = forwarded_attrs(variant);
+    let deprecated: Vec<_> = deprecated_attrs(variant).collect();
+    let attrs = quote!(#forwarded #(#deprecated)*);
+
+    let mut methods = quote! {
+        #[doc = #is_doc]
+        #attrs
+        #[inline]
+        #vis fn #is(&self) -> bool {
+            ::core::matches!(self, Self::#variant_name { .. })
+        }
+    };
+
+    let Fields::Unnamed(fields) = &variant.fields else {
+        return methods;
+    };
+
+    let as_ref = format_ident!("as_{}", snake);
+    let as_mut = format_ident!("as_{}_mut", snake);
+    let into = format_ident!("into_{}", snake);
+    let as_ref_doc = format!(
+        "Returns a reference to the value of [`Self::{}`], if it is one.",
+        variant_name
+    );
+    let as_mut_doc = format!(
+        "Returns a mutable reference to the value of [`Self::{}`], if it is one.",
+        variant_name
+    );
+    let into_doc = format!(
+        "Returns the value of [`Self::{}`], or `self` back if it's another variant.",
+        variant_name
+    );
+
+    let types: Vec<_> = fields.unnamed.iter().map(|field| &field.ty).collect();
+    let values: Vec<_> = (0..types.len())
+        .map(|i| format_ident!("value{}", i))
+        .collect();
+    let (ty, ref_ty, mut_ty, value) = if types.len() == 1 {
+        let ty = types[0];
+        (quote!(#ty), quote!(&#ty), quote!(&mut #ty), quote!(value0))
+    } else {
+        (
+            quote!((#(#types),*)),
+            quote!((#(&#types),*)),
+            quote!((#(&mut #types),*)),
+            quote!((#(#values),*)),
+        )
+    };
+
+    methods.extend(quote! {
+        #[doc = #as_ref_doc]
+        #attrs
+        #[inline]
+        #vis fn #as_ref(&self) -> ::core::option::Option<#ref_ty> {
+            match self {
+                Self::#variant_name(#(#values),*) => ::core::option::Option::Some(#value),
+                #[allow(unreachable_patterns)]
+                _ => ::core::option::Option::None,
+            }
+        }
+
+        #[doc = #as_mut_doc]
+        #attrs
+        #[inline]
+        #vis fn #as_mut(&mut self) -> ::core::option::Option<#mut_ty> {
+            match self {
+                Self::#variant_name(#(#values),*) => ::core::option::Option::Some(#value),
+                #[allow(unreachable_patterns)]
+                _ => ::core::option::Option::None,
+            }
+        }
+
+        #[doc = #into_doc]
+        #attrs
+        #[inline]
+        #vis fn #into(self) -> ::core::result::Result<#ty, Self> {
+            match self {
+                Self::#variant_name(#(#values),*) => ::core::result::Result::Ok(#value),
+                #[allow(unreachable_patterns)]
+                other => ::core::result::Result::Err(other),
+            }
+        }
+    });
+
+    methods
+}
+
+/// Converts an UpperCamelCase name into snake_case.
+///
+/// Acronyms are kept together, so `IOError` becomes `io_error`.
+fn snake_case(name: &str) -> String {
+    let name = name.strip_prefix("r#").unwrap_or(name);
+    let chars: Vec<char> = name.chars().collect();
+    let mut result = String::with_capacity(name.len() + 4);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if prev != '_'
+                && (prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next_is_lower))
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snake_case_names() {
+        assert_eq!(snake_case("Io"), "io");
+        assert_eq!(snake_case("ParseInt"), "parse_int");
+        assert_eq!(snake_case("IOError"), "io_error");
+        assert_eq!(snake_case("Utf8Error"), "utf8_error");
+        assert_eq!(snake_case("Http2"), "http2");
+        assert_eq!(snake_case("r#Type"), "type");
+    }
+}
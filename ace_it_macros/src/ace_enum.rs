@@ -0,0 +1,225 @@
+//! Declaration of enums from a list of the types they wrap, with `ace_enum!`.
+
+use std::collections::HashMap;
+
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    Attribute, Generics, ItemEnum, Token, Type, Visibility,
+};
+
+use crate::{expand, normalize, option_args, Errors, Mode};
+
+/// An enum declared as `#[attrs] vis enum Name<generics> = Type | Type as Name;`.
+struct Declaration {
+    attrs: Vec<Attribute>,
+    vis: Visibility,
+    enum_token: Token![enum],
+    ident: Ident,
+    generics: Generics,
+    variants: Vec<Member>,
+}
+
+/// A type the enum wraps, with the attributes and the name of its variant.
+struct Member {
+    attrs: Vec<Attribute>,
+    ty: Type,
+    /// The name set with `as Name`.
+    name: Option<Ident>,
+}
+
+impl Parse for Declaration {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let attrs = input.call(Attribute::parse_outer)?;
+        let vis = input.parse()?;
+        let enum_token = input.parse()?;
+        let ident = input.parse()?;
+        let mut generics: Generics = input.parse()?;
+        generics.where_clause = input.parse()?;
+        input.parse::<Token![=]>()?;
+        if input.peek(Token![|]) {
+            input.parse::<Token![|]>()?;
+        }
+
+        let mut variants = Vec::new();
+        loop {
+            let attrs = input.call(Attribute::parse_outer)?;
+            let ty = input.parse()?;
+            let name = if input.peek(Token![as]) {
+                input.parse::<Token![as]>()?;
+                Some(input.parse()?)
+            } else {
+                None
+            };
+            variants.push(Member { attrs, ty, name });
+
+            if !input.peek(Token![|]) {
+                break;
+            }
+            input.parse::<Token![|]>()?;
+        }
+        input.parse::<Token![;]>()?;
+
+        Ok(Self {
+            attrs,
+            vis,
+            enum_token,
+            ident,
+            generics,
+            variants,
+        })
+    }
+}
+
+/// The input of `ace_enum!`: any number of declarations.
+pub(crate) struct Declarations(Vec<Declaration>);
+
+impl Parse for Declarations {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut declarations = Vec::new();
+        while !input.is_empty() {
+            declarations.push(input.parse()?);
+        }
+        Ok(Self(declarations))
+    }
+}
+
+impl Declarations {
+    /// Expands every declared enum like `#[ace_it]` would.
+    pub(crate) fn expand(self) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        for declaration in self.0 {
+            let mut errors = Errors::default();
+            let args = option_args(&declaration.attrs).unwrap_or_else(|e| {
+                errors.push(e);
+                TokenStream::new()
+            });
+            let item = declaration.into_item(&mut errors);
+            expand(Mode::Attribute, args, item).to_tokens(&mut tokens);
+            errors.to_tokens(&mut tokens);
+        }
+        tokens
+    }
+}
+
+impl Declaration {
+    /// Returns the declared enum, leaving out the variants that can't be named or share a name with another one.
+    fn into_item(self, errors: &mut Errors) -> ItemEnum {
+        let mut names: HashMap<String, Type> = HashMap::new();
+        let mut variants = Vec::new();
+
+        for Member { attrs, ty, name } in self.variants {
+            let Some(name) = name.or_else(|| variant_name(&ty)) else {
+                errors.push(syn::Error::new_spanned(
+                    &ty,
+                    format!(
+                        "Can't name a variant after `{}`, name it with `as Name`",
+                        normalize::type_name(&ty),
+                    ),
+                ));
+                continue;
+            };
+
+            if let Some(first) = names.get(&name.to_string()) {
+                errors.push(syn::Error::new(
+                    name.span(),
+                    format!(
+                        "Duplicate variant name `{}`, it's already the name of the variant wrapping `{}`. Name one of them with `as Name`",
+                        name,
+                        normalize::type_name(first),
+                    ),
+                ));
+                errors.push(syn::Error::new_spanned(
+                    first,
+                    format!(
+                        "note: variant `{}` wraps `{}` first",
+                        name,
+                        normalize::type_name(first)
+                    ),
+                ));
+                continue;
+            }
+            names.insert(name.to_string(), ty.clone());
+
+            variants.push(quote!(#(#attrs)* #name(#ty)));
+        }
+
+        let Declaration {
+            attrs,
+            vis,
+            enum_token,
+            ident,
+            generics,
+            ..
+        } = self;
+        let attrs = attrs.iter().filter(|attr| !attr.path.is_ident("ace_it"));
+        let where_clause = &generics.where_clause;
+        syn::parse_quote! {
+            #(#attrs)*
+            #vis #enum_token #ident #generics #where_clause {
+                #(#variants),*
+            }
+        }
+    }
+}
+
+/// Returns the name of the variant wrapping the type, the last segment of its path.
+fn variant_name(ty: &Type) -> Option<Ident> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident.clone()),
+        Type::Group(group) => variant_name(&group.elem),
+        Type::Paren(paren) => variant_name(&paren.elem),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse2;
+
+    #[test]
+    fn declared_enum() {
+        let input = quote! {
+            #[derive(Debug)]
+            #[ace_it(error)]
+            pub enum Error<T> = std::io::Error | Vec<T> | #[ace_it(skip)] String as Message | &'static str as Static;
+        };
+        let declarations: Declarations = parse2(input).unwrap();
+        let mut errors = Errors::default();
+        let item = declarations
+            .0
+            .into_iter()
+            .next()
+            .unwrap()
+            .into_item(&mut errors);
+        let expected = quote! {
+            #[derive(Debug)]
+            pub enum Error<T> {
+                Error(std::io::Error),
+                Vec(Vec<T>),
+                #[ace_it(skip)]
+                Message(String),
+                Static(&'static str)
+            }
+        };
+        assert!(errors.0.is_none());
+        assert_eq!(item.to_token_stream().to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn variant_name_errors() {
+        let input = quote! {
+            enum Error = std::io::Error | std::fmt::Error | &'static str;
+        };
+        let result = parse2::<Declarations>(input).unwrap().expand().to_string();
+        assert!(result.contains("Duplicate variant name `Error`"));
+        assert!(result.contains("Can't name a variant after `&'static str`"));
+        assert!(result.contains(&quote!(impl From<std::io::Error> for Error).to_string()));
+    }
+}
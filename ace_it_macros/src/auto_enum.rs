@@ -0,0 +1,277 @@
+//! Functions returning values of different types from their branches, with `#[auto_enum(Trait, ...)]`.
+//!
+//! The expressions marked with `ace!(...)`, and the tails of the branches of a trailing `if` or `match`,
+//! become variants of an enum declared in the function. The enum wraps one type parameter per variant
+//! and is expanded with `#[ace_it(delegate(Trait, ...))]`.
+
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, ToTokens};
+use syn::{
+    parse_quote, parse_quote_spanned,
+    punctuated::Punctuated,
+    spanned::Spanned,
+    visit_mut::{self, VisitMut},
+    Block, Expr, ExprMacro, Item, ItemFn, Path, Stmt, Token,
+};
+
+use crate::Errors;
+
+/// The traits the enum implements, set with `#[auto_enum(Trait, ...)]`.
+pub(crate) struct Traits(Punctuated<Path, Token![,]>);
+
+impl syn::parse::Parse for Traits {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        Ok(Self(Punctuated::parse_terminated(input)?))
+    }
+}
+
+/// Returns true if the macro marks an expression to put in the enum.
+fn is_marker(mac: &syn::Macro) -> bool {
+    mac.path.is_ident("ace")
+}
+
+/// Returns true if the expression never produces a value, so it's left out of the enum.
+fn diverges(expr: &Expr) -> bool {
+    match expr {
+        Expr::Return(_) | Expr::Break(_) | Expr::Continue(_) => true,
+        Expr::Macro(mac) => mac.mac.path.segments.last().is_some_and(|last| {
+            ["panic", "unreachable", "todo", "unimplemented"].contains(&&*last.ident.to_string())
+        }),
+        _ => false,
+    }
+}
+
+/// Turns a trailing macro statement back into an expression, which is how it's parsed at the end of a block.
+fn macro_stmt_to_expr(stmt: &mut Stmt) {
+    if let Stmt::Item(Item::Macro(item)) = stmt {
+        if item.ident.is_none() {
+            let expr = Expr::Macro(ExprMacro {
+                attrs: item.attrs.clone(),
+                mac: item.mac.clone(),
+            });
+            *stmt = match item.semi_token {
+                Some(semi) => Stmt::Semi(expr, semi),
+                None => Stmt::Expr(expr),
+            };
+        }
+    }
+}
+
+/// Marks the tails of the branches of the expression with `ace!(...)`.
+fn mark_tail(expr: &mut Expr) {
+    match expr {
+        Expr::If(expr) => {
+            mark_block(&mut expr.then_branch);
+            if let Some((_, else_branch)) = &mut expr.else_branch {
+                mark_tail(else_branch);
+            }
+        }
+        Expr::Match(expr) => {
+            for arm in &mut expr.arms {
+                mark_tail(&mut arm.body);
+            }
+        }
+        Expr::Block(block) if block.label.is_none() => mark_block(&mut block.block),
+        Expr::Macro(mac) if is_marker(&mac.mac) => {}
+        expr if diverges(expr) => {}
+        expr => *expr = parse_quote_spanned!(expr.span()=> ace!(#expr)),
+    }
+}
+
+/// Marks the tail of the block with `ace!(...)`, along with the tails of its branches.
+fn mark_block(block: &mut Block) {
+    let Some(last) = block.stmts.last_mut() else {
+        return;
+    };
+    macro_stmt_to_expr(last);
+    if let Stmt::Expr(expr) = last {
+        mark_tail(expr);
+    }
+}
+
+/// Replaces the `ace!(...)` markers with the variants of the enum, numbered in order.
+///
+/// Closures, async blocks and nested items are left alone, their markers aren't for this function.
+#[derive(Default)]
+struct Markers {
+    count: usize,
+    errors: Errors,
+}
+
+impl VisitMut for Markers {
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        match expr {
+            Expr::Closure(_) | Expr::Async(_) => {}
+            Expr::Macro(mac) if is_marker(&mac.mac) => match mac.mac.parse_body::<Expr>() {
+                Ok(mut value) => {
+                    self.visit_expr_mut(&mut value);
+                    let variant = format_ident!("__AceIt{}", self.count);
+                    self.count += 1;
+                    *expr = parse_quote_spanned!(mac.span()=> __AceItAuto::#variant(#value));
+                }
+                Err(e) => self.errors.push(e),
+            },
+            _ => visit_mut::visit_expr_mut(self, expr),
+        }
+    }
+
+    fn visit_stmt_mut(&mut self, stmt: &mut Stmt) {
+        if matches!(stmt, Stmt::Item(Item::Macro(item)) if is_marker(&item.mac)) {
+            macro_stmt_to_expr(stmt);
+        }
+        visit_mut::visit_stmt_mut(self, stmt);
+    }
+
+    fn visit_item_mut(&mut self, _: &mut Item) {}
+}
+
+/// Generates the function with the enum declared in it and its branches wrapped in the variants.
+pub(crate) fn process_auto_enum(traits: Traits, mut item: ItemFn) -> TokenStream {
+    let mut errors = Errors::default();
+    let mut traits: Vec<_> = traits.0.into_iter().collect();
+    if traits.is_empty() {
+        errors.push(syn::Error::new(
+            Span::call_site(),
+            "`#[auto_enum]` needs the traits the enum implements, like `#[auto_enum(Iterator)]`",
+        ));
+    }
+    // `Error` requires `Debug` and `Display`, which the enum can only get by delegating them too.
+    let named = |traits: &[Path], name: &str| {
+        traits
+            .iter()
+            .any(|path| path.segments.last().is_some_and(|last| last.ident == name))
+    };
+    if named(&traits, "Error") {
+        for required in ["Debug", "Display"] {
+            if !named(&traits, required) {
+                let required = format_ident!("{}", required);
+                traits.push(parse_quote!(#required));
+            }
+        }
+    }
+
+    if let Some(last) = item.block.stmts.last_mut() {
+        macro_stmt_to_expr(last);
+        if let Stmt::Expr(expr @ (Expr::If(_) | Expr::Match(_))) = last {
+            mark_tail(expr);
+        }
+    }
+
+    let mut markers = Markers::default();
+    markers.visit_block_mut(&mut item.block);
+    if let Errors(Some(error)) = markers.errors {
+        errors.push(error);
+    }
+    if markers.count < 2 {
+        errors.push(syn::Error::new_spanned(
+            &item.sig,
+            "`#[auto_enum]` needs at least two expressions to put in the enum. Mark them with `ace!(...)`, or end the function with an `if` or a `match`",
+        ));
+    }
+
+    if errors.0.is_none() {
+        let variants = (0..markers.count).map(|index| format_ident!("__AceIt{}", index));
+        let params = variants.clone();
+        let declaration: Stmt = parse_quote! {
+            #[::ace_it::ace_it(delegate(#(#traits),*))]
+            enum __AceItAuto<#(#params),*> {
+                #(
+                    #[ace_it(skip)]
+                    #variants(#variants),
+                )*
+            }
+        };
+        item.block.stmts.insert(0, declaration);
+    }
+
+    let mut tokens = item.to_token_stream();
+    errors.to_tokens(&mut tokens);
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use quote::quote;
+    use syn::parse2;
+
+    #[test]
+    fn wraps_branches_and_markers() {
+        let input = quote! {
+            fn numbers(kind: u8) -> impl Iterator<Item = u8> {
+                if kind == 0 {
+                    return ace!(std::iter::empty());
+                }
+                match kind {
+                    1 => std::iter::once(1),
+                    2 => {
+                        let numbers = vec![1, 2];
+                        numbers.into_iter()
+                    }
+                    3 => panic!("unsupported"),
+                    _ => if kind % 2 == 0 { 0..kind } else { unreachable!() },
+                }
+            }
+        };
+        let result = process_auto_enum(parse2(quote!(Iterator)).unwrap(), parse2(input).unwrap())
+            .to_string();
+        let declaration = quote! {
+            #[::ace_it::ace_it(delegate(Iterator))]
+            enum __AceItAuto<__AceIt0, __AceIt1, __AceIt2, __AceIt3> {
+                #[ace_it(skip)]
+                __AceIt0(__AceIt0),
+                #[ace_it(skip)]
+                __AceIt1(__AceIt1),
+                #[ace_it(skip)]
+                __AceIt2(__AceIt2),
+                #[ace_it(skip)]
+                __AceIt3(__AceIt3),
+            }
+        };
+        let body = quote! {
+            match kind {
+                1 => __AceItAuto::__AceIt1(std::iter::once(1)),
+                2 => {
+                    let numbers = vec![1, 2];
+                    __AceItAuto::__AceIt2(numbers.into_iter())
+                }
+                3 => panic!("unsupported"),
+                _ => if kind % 2 == 0 { __AceItAuto::__AceIt3(0..kind) } else { unreachable!() },
+            }
+        };
+        assert!(result.contains(&declaration.to_string()));
+        assert!(
+            result.contains(&quote!(return __AceItAuto::__AceIt0(std::iter::empty());).to_string())
+        );
+        assert!(result.contains(&body.to_string()));
+    }
+
+    #[test]
+    fn error_brings_debug_and_display() {
+        let input = quote! {
+            fn error(io: bool) -> impl std::error::Error {
+                if io { ace!(std::io::Error::other("io")) } else { ace!(std::fmt::Error) }
+            }
+        };
+        let result = process_auto_enum(
+            parse2(quote!(std::error::Error)).unwrap(),
+            parse2(input).unwrap(),
+        )
+        .to_string();
+        assert!(result.contains(&quote!(delegate(std::error::Error, Debug, Display)).to_string()));
+    }
+
+    #[test]
+    fn single_expression_error() {
+        let input = quote! {
+            fn numbers() -> impl Iterator<Item = u8> {
+                let numbers = || ace!(0..1);
+                numbers()
+            }
+        };
+        let result = process_auto_enum(parse2(quote!(Iterator)).unwrap(), parse2(input).unwrap())
+            .to_string();
+        assert!(result.contains("needs at least two expressions"));
+        assert!(!result.contains("__AceItAuto"));
+    }
+}
@@ -0,0 +1,933 @@
+//! Implementation of traits for the enum by forwarding to the value of every variant,
+//! set with `#[ace_it(delegate(Trait, ...))]`.
+//!
+//! The methods of well-known std traits are known up front. Other traits have to be marked with `#[ace_trait]`,
+//! which generates a macro named after the trait. The enum isn't expanded right away, it calls that macro instead,
+//! which calls back `__ace_it_delegate` with the definition of the trait.
+
+use proc_macro2::{Ident, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    parse_quote,
+    punctuated::Punctuated,
+    visit_mut::{self, VisitMut},
+    ConstParam, FnArg, GenericParam, Generics, ItemEnum, ItemTrait, Lifetime, Pat, Path, Signature,
+    Token, TraitItem, Type, TypeParam, Variant,
+};
+
+use crate::{forwarded_attrs, member_type, wrapped_field, Errors, VariantOptions};
+
+/// A trait to delegate, with the definition passed back by its macro once it's resolved.
+pub(crate) struct Delegate {
+    path: Path,
+    definition: Option<ItemTrait>,
+}
+
+impl Parse for Delegate {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let path = input.parse()?;
+        let definition = if input.peek(syn::token::Brace) {
+            let definition;
+            syn::braced!(definition in input);
+            Some(definition.parse()?)
+        } else {
+            None
+        };
+        Ok(Self { path, definition })
+    }
+}
+
+impl ToTokens for Delegate {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.path.to_tokens(tokens);
+        if let Some(definition) = &self.definition {
+            tokens.extend(quote!({ #definition }));
+        }
+    }
+}
+
+impl Delegate {
+    /// Returns the path of the trait to implement and its definition, or [None] if it still has to be resolved.
+    fn resolved(&self) -> Option<(Path, ItemTrait)> {
+        match &self.definition {
+            Some(definition) => Some((self.path.clone(), definition.clone())),
+            None => builtin(&self.path),
+        }
+    }
+}
+
+/// Returns the path and the definition of a well-known std trait,
+/// named by itself or by a path ending in its module, like `io::Read` or `std::io::Read`.
+fn builtin(path: &Path) -> Option<(Path, ItemTrait)> {
+    let segments: Vec<_> = path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect();
+    let (module, definition): (&[&str], ItemTrait) = match segments.last()?.as_str() {
+        "Read" => (
+            &["std", "io"],
+            parse_quote! {
+                trait Read {
+                    fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize>;
+                    fn read_vectored(&mut self, bufs: &mut [::std::io::IoSliceMut<'_>]) -> ::std::io::Result<usize>;
+                    fn read_to_end(&mut self, buf: &mut ::std::vec::Vec<u8>) -> ::std::io::Result<usize>;
+                    fn read_to_string(&mut self, buf: &mut ::std::string::String) -> ::std::io::Result<usize>;
+                    fn read_exact(&mut self, buf: &mut [u8]) -> ::std::io::Result<()>;
+                }
+            },
+        ),
+        "BufRead" => (
+            &["std", "io"],
+            parse_quote! {
+                trait BufRead: Read {
+                    fn fill_buf(&mut self) -> ::std::io::Result<&[u8]>;
+                    fn consume(&mut self, amt: usize);
+                    fn read_until(&mut self, byte: u8, buf: &mut ::std::vec::Vec<u8>) -> ::std::io::Result<usize>;
+                    fn read_line(&mut self, buf: &mut ::std::string::String) -> ::std::io::Result<usize>;
+                }
+            },
+        ),
+        "Write" => (
+            &["std", "io"],
+            parse_quote! {
+                trait Write {
+                    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize>;
+                    fn write_vectored(&mut self, bufs: &[::std::io::IoSlice<'_>]) -> ::std::io::Result<usize>;
+                    fn flush(&mut self) -> ::std::io::Result<()>;
+                    fn write_all(&mut self, buf: &[u8]) -> ::std::io::Result<()>;
+                    fn write_fmt(&mut self, fmt: ::core::fmt::Arguments<'_>) -> ::std::io::Result<()>;
+                }
+            },
+        ),
+        "Seek" => (
+            &["std", "io"],
+            parse_quote! {
+                trait Seek {
+                    fn seek(&mut self, pos: ::std::io::SeekFrom) -> ::std::io::Result<u64>;
+                    fn stream_position(&mut self) -> ::std::io::Result<u64>;
+                }
+            },
+        ),
+        "Iterator" => (
+            &["core", "iter"],
+            parse_quote! {
+                trait Iterator {
+                    type Item;
+                    fn next(&mut self) -> ::core::option::Option<Self::Item>;
+                    fn size_hint(&self) -> (usize, ::core::option::Option<usize>);
+                    fn nth(&mut self, n: usize) -> ::core::option::Option<Self::Item>;
+                    // Named so they don't clash with the generics of the enum.
+                    fn fold<__AceItB, __AceItF>(self, init: __AceItB, f: __AceItF) -> __AceItB
+                    where
+                        __AceItF: ::core::ops::FnMut(__AceItB, Self::Item) -> __AceItB;
+                }
+            },
+        ),
+        "DoubleEndedIterator" => (
+            &["core", "iter"],
+            parse_quote! {
+                trait DoubleEndedIterator: Iterator {
+                    fn next_back(&mut self) -> ::core::option::Option<<Self as ::core::iter::Iterator>::Item>;
+                }
+            },
+        ),
+        "ExactSizeIterator" => (
+            &["core", "iter"],
+            parse_quote! {
+                trait ExactSizeIterator: Iterator {
+                    fn len(&self) -> usize;
+                }
+            },
+        ),
+        "Future" => (
+            &["core", "future"],
+            parse_quote! {
+                trait Future {
+                    type Output;
+                    fn poll(
+                        self: ::core::pin::Pin<&mut Self>,
+                        cx: &mut ::core::task::Context<'_>,
+                    ) -> ::core::task::Poll<Self::Output>;
+                }
+            },
+        ),
+        "Debug" => (
+            &["core", "fmt"],
+            parse_quote! {
+                trait Debug {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result;
+                }
+            },
+        ),
+        "Display" => (
+            &["core", "fmt"],
+            parse_quote! {
+                trait Display {
+                    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result;
+                }
+            },
+        ),
+        "Error" => (
+            &["std", "error"],
+            parse_quote! {
+                trait Error: Debug + Display {
+                    fn source(&self) -> ::core::option::Option<&(dyn ::std::error::Error + 'static)>;
+                }
+            },
+        ),
+        _ => return None,
+    };
+
+    // `std` re-exports everything in `core`, so either can start the path.
+    let mut named = segments.as_slice();
+    if let [root, rest @ ..] = named {
+        if rest.len() >= module.len() && (root == "std" || root == "core") {
+            named = rest;
+        }
+    }
+    let known = &module[1..];
+    let modules = &named[..named.len() - 1];
+    if modules.len() > known.len()
+        || !known[known.len() - modules.len()..]
+            .iter()
+            .zip(modules)
+            .all(|(known, named)| known == named)
+    {
+        return None;
+    }
+
+    let crate_name = format_ident!("{}", module[0]);
+    let modules = known.iter().map(|module| format_ident!("{}", module));
+    let ident = &definition.ident;
+    Some((
+        parse_quote!(::#crate_name #(::#modules)* ::#ident),
+        definition,
+    ))
+}
+
+/// How a delegated method takes `self`.
+enum Receiver {
+    /// `self`, `&self` or `&mut self`, matched as it is.
+    Plain,
+    /// `self: Pin<&mut Self>`, matched through the pin.
+    Pinned,
+}
+
+/// Returns how the method takes `self`, or [None] if it doesn't.
+fn receiver(sig: &Signature) -> syn::Result<Option<Receiver>> {
+    let ty = match sig.inputs.first() {
+        Some(FnArg::Receiver(_)) => return Ok(Some(Receiver::Plain)),
+        Some(FnArg::Typed(arg)) if matches!(&*arg.pat, Pat::Ident(pat) if pat.ident == "self") => {
+            &arg.ty
+        }
+        _ => return Ok(None),
+    };
+
+    // Types are compared by their tokens, which don't carry spans in their string.
+    let tokens = |tokens: &dyn ToTokens| tokens.to_token_stream().to_string();
+    let plain = [quote!(Self), quote!(&Self), quote!(&mut Self)];
+    if plain.iter().any(|plain| tokens(plain) == tokens(ty)) {
+        return Ok(Some(Receiver::Plain));
+    }
+    if let Type::Path(path) = &**ty {
+        let last = path.path.segments.last();
+        if let Some(syn::PathArguments::AngleBracketed(args)) = last.map(|last| &last.arguments) {
+            if last.is_some_and(|last| last.ident == "Pin")
+                && args.args.len() == 1
+                && tokens(&args.args[0]) == tokens(&quote!(&mut Self))
+            {
+                return Ok(Some(Receiver::Pinned));
+            }
+        }
+    }
+    Err(syn::Error::new_spanned(
+        ty,
+        format!(
+            "Can't delegate `fn {}`, `self` can only be taken by value, by reference or as `Pin<&mut Self>`",
+            sig.ident
+        ),
+    ))
+}
+
+/// Returns true if the tokens name `Self` itself, rather than a path in it like `Self::Item`.
+fn mentions_self(tokens: TokenStream) -> bool {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Ident(ident)
+                if ident == "Self"
+                    && !matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':') =>
+            {
+                return true;
+            }
+            TokenTree::Group(group) if mentions_self(group.stream()) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Returns the definition of a trait marked with `#[ace_trait]` that's passed to the enums delegating it.
+///
+/// That's the methods taking `self` and the associated types, without their bodies and defaults.
+/// Methods that don't take `self` and associated consts are left out if they have a default, and are errors otherwise.
+fn delegated_definition(item: &ItemTrait) -> syn::Result<ItemTrait> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &item.generics,
+            "`#[ace_trait]` doesn't support generic traits",
+        ));
+    }
+
+    let mut errors = Errors::default();
+    let mut items = Vec::new();
+    for trait_item in &item.items {
+        match trait_item {
+            TraitItem::Method(method) => match receiver(&method.sig) {
+                Ok(Some(_)) => {
+                    let sig = &method.sig;
+                    let inputs = sig.inputs.iter().skip(1).map(|arg| match arg {
+                        FnArg::Typed(arg) => arg.ty.to_token_stream(),
+                        FnArg::Receiver(_) => TokenStream::new(),
+                    });
+                    let output = sig.output.to_token_stream();
+                    if inputs.chain([output]).any(mentions_self) {
+                        errors.push(syn::Error::new_spanned(
+                            sig,
+                            format!(
+                                "Can't delegate `fn {}`, as it takes or returns `Self` other than as `self`",
+                                sig.ident
+                            ),
+                        ));
+                        continue;
+                    }
+                    let attrs = method.attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+                    items.push(quote!(#(#attrs)* #sig;));
+                }
+                Ok(None) if method.default.is_some() => {}
+                Ok(None) => errors.push(syn::Error::new_spanned(
+                    &method.sig,
+                    format!(
+                        "Can't delegate `fn {}`, as it doesn't take `self`. Give it a default to leave it out",
+                        method.sig.ident
+                    ),
+                )),
+                Err(e) => errors.push(e),
+            },
+            TraitItem::Type(ty) if !ty.generics.params.is_empty() => {
+                errors.push(syn::Error::new_spanned(
+                    &ty.generics,
+                    "`#[ace_trait]` doesn't support generic associated types",
+                ))
+            }
+            TraitItem::Type(ty) => {
+                let ident = &ty.ident;
+                items.push(quote!(type #ident;));
+            }
+            TraitItem::Const(constant) if constant.default.is_some() => {}
+            TraitItem::Const(constant) => errors.push(syn::Error::new_spanned(
+                constant,
+                format!(
+                    "Can't delegate `const {}`, each variant could have a different value. Give it a default to leave it out",
+                    constant.ident
+                ),
+            )),
+            trait_item => errors.push(syn::Error::new_spanned(
+                trait_item,
+                "`#[ace_trait]` can't delegate this item",
+            )),
+        }
+    }
+
+    if let Errors(Some(error)) = errors {
+        return Err(error);
+    }
+    let unsafety = &item.unsafety;
+    let ident = &item.ident;
+    Ok(parse_quote!(#unsafety trait #ident { #(#items)* }))
+}
+
+/// Generates the trait marked with `#[ace_trait]` along with its macro,
+/// which passes its definition back to the enums delegating it.
+pub(crate) fn process_trait(item: ItemTrait) -> TokenStream {
+    let definition = match delegated_definition(&item) {
+        Ok(definition) => definition,
+        Err(e) => {
+            let error = e.to_compile_error();
+            return quote!(#item #error);
+        }
+    };
+
+    let ident = &item.ident;
+    let macro_name = format_ident!("__ace_it_trait_{}", ident);
+    quote! {
+        #item
+        #[allow(unused_macros)]
+        macro_rules! #macro_name {
+            (delegate $mode:ident { $($args:tt)* } { $($item:tt)* }) => {
+                ::ace_it::__ace_it_delegate! { $mode { $($args)* } { $($item)* } { #definition } }
+            };
+        }
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #macro_name as #ident;
+    }
+}
+
+/// Returns the call to the macro of the first delegated trait that isn't resolved yet,
+/// or [None] if they all are.
+pub(crate) fn delegate_chain(
+    delegates: &[Delegate],
+    mode: &Ident,
+    args: &TokenStream,
+    item: &ItemEnum,
+) -> Option<TokenStream> {
+    let delegate = delegates
+        .iter()
+        .find(|delegate| delegate.resolved().is_none())?;
+    let mut path = delegate.path.clone();
+    for segment in &mut path.segments {
+        segment.arguments = syn::PathArguments::None;
+    }
+    Some(quote! {
+        #path! { delegate #mode { #args } { #item } }
+    })
+}
+
+/// The input of `__ace_it_delegate`: how the enum is expanded, the options of the enum, the enum itself
+/// and the definition of the trait, which the options get for the first unresolved trait.
+pub(crate) struct Resolved {
+    pub(crate) mode: Ident,
+    pub(crate) args: TokenStream,
+    pub(crate) item: ItemEnum,
+}
+
+impl Parse for Resolved {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mode = input.parse()?;
+        let args;
+        syn::braced!(args in input);
+        let item;
+        syn::braced!(item in input);
+        let definition;
+        syn::braced!(definition in input);
+        let mut definition = Some(definition.parse()?);
+        Ok(Self {
+            mode,
+            args: resolve_args(args.parse()?, &mut definition)?,
+            item: item.parse()?,
+        })
+    }
+}
+
+/// Puts the definition after the first unresolved trait in the `delegate(...)` options.
+fn resolve_args(args: TokenStream, definition: &mut Option<ItemTrait>) -> syn::Result<TokenStream> {
+    let mut resolved = TokenStream::new();
+    let mut after_delegate = false;
+    for token in args {
+        match token {
+            TokenTree::Group(group) if after_delegate && definition.is_some() => {
+                let mut delegates = syn::parse::Parser::parse2(
+                    Punctuated::<Delegate, Token![,]>::parse_terminated,
+                    group.stream(),
+                )?;
+                if let Some(delegate) = delegates
+                    .iter_mut()
+                    .find(|delegate| delegate.resolved().is_none())
+                {
+                    delegate.definition = definition.take();
+                }
+                let mut resolved_group =
+                    proc_macro2::Group::new(group.delimiter(), delegates.to_token_stream());
+                resolved_group.set_span(group.span());
+                resolved.extend([TokenTree::Group(resolved_group)]);
+                after_delegate = false;
+            }
+            token => {
+                after_delegate = matches!(&token, TokenTree::Ident(ident) if ident == "delegate");
+                resolved.extend([token]);
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Renames the generic parameters of the method named like ones of the enum, which the impl already declares.
+///
+/// They're renamed like the builtin ones, `T` to `__AceItT` and `'a` to `'__ace_it_a`, everywhere in the signature.
+fn rename_enum_generics(sig: &mut Signature, generics: &Generics) {
+    let taken = |ident: &Ident| {
+        generics.params.iter().any(|param| match param {
+            GenericParam::Type(param) => param.ident == *ident,
+            GenericParam::Lifetime(param) => param.lifetime.ident == *ident,
+            GenericParam::Const(param) => param.ident == *ident,
+        })
+    };
+    let mut renames = Renames::default();
+    for param in &sig.generics.params {
+        match param {
+            GenericParam::Type(TypeParam { ident, .. })
+            | GenericParam::Const(ConstParam { ident, .. })
+                if taken(ident) =>
+            {
+                renames
+                    .idents
+                    .push((ident.clone(), format_ident!("__AceIt{}", ident)));
+            }
+            GenericParam::Lifetime(param) if taken(&param.lifetime.ident) => {
+                let ident = &param.lifetime.ident;
+                renames
+                    .lifetimes
+                    .push((ident.clone(), format_ident!("__ace_it_{}", ident)));
+            }
+            _ => {}
+        }
+    }
+    if !renames.idents.is_empty() || !renames.lifetimes.is_empty() {
+        renames.visit_signature_mut(sig);
+    }
+}
+
+/// Renames generic parameters, where they're declared and where paths and lifetimes name them.
+#[derive(Default)]
+struct Renames {
+    idents: Vec<(Ident, Ident)>,
+    lifetimes: Vec<(Ident, Ident)>,
+}
+
+impl VisitMut for Renames {
+    fn visit_type_param_mut(&mut self, param: &mut TypeParam) {
+        rename(&self.idents, &mut param.ident);
+        visit_mut::visit_type_param_mut(self, param);
+    }
+
+    fn visit_const_param_mut(&mut self, param: &mut ConstParam) {
+        rename(&self.idents, &mut param.ident);
+        visit_mut::visit_const_param_mut(self, param);
+    }
+
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        if path.leading_colon.is_none() {
+            if let Some(first) = path.segments.first_mut() {
+                rename(&self.idents, &mut first.ident);
+            }
+        }
+        visit_mut::visit_path_mut(self, path);
+    }
+
+    fn visit_lifetime_mut(&mut self, lifetime: &mut Lifetime) {
+        rename(&self.lifetimes, &mut lifetime.ident);
+    }
+}
+
+/// Replaces the identifier with its new name, if it has one.
+fn rename(renames: &[(Ident, Ident)], ident: &mut Ident) {
+    if let Some((_, renamed)) = renames.iter().find(|(name, _)| name == ident) {
+        *ident = renamed.clone();
+    }
+}
+
+/// Generates the impl of the trait, forwarding each method to the value of every variant.
+///
+/// Every variant has to wrap a single value, and the associated types are the ones of the first variant.
+pub(crate) fn process_delegate<'a>(
+    delegate: &Delegate,
+    variants: impl Iterator<Item = (&'a Variant, &'a VariantOptions)>,
+    enum_name: &Ident,
+    generics: &Generics,
+) -> syn::Result<TokenStream> {
+    let Some((path, definition)) = delegate.resolved() else {
+        return Err(syn::Error::new_spanned(
+            &delegate.path,
+            "Can only delegate well-known std traits and traits marked with `#[ace_trait]`",
+        ));
+    };
+
+    let mut errors = Errors::default();
+    let mut wrapped = Vec::new();
+    for (variant, options) in variants {
+        let field = wrapped_field(variant, options).and_then(|member| {
+            let ty = member_type(variant, &member)?.clone();
+            Some((member, ty))
+        });
+        match field {
+            Some((member, ty)) => wrapped.push((variant, member, ty)),
+            None => errors.push(syn::Error::new(
+                variant.ident.span(),
+                format!(
+                    "Variant `{}` doesn't wrap a single value to delegate `{}` to",
+                    variant.ident,
+                    path.segments
+                        .last()
+                        .map_or(String::new(), |last| last.ident.to_string()),
+                ),
+            )),
+        }
+    }
+    if let Errors(Some(error)) = errors {
+        return Err(error);
+    }
+
+    // Variants left out by a `cfg` can't be bounded on, as where clauses can't carry it.
+    let bounded: Vec<_> = wrapped
+        .iter()
+        .filter(|(variant, ..)| forwarded_attrs(variant).is_empty())
+        .map(|(_, _, ty)| ty)
+        .collect();
+    let associated = associated_types(&definition);
+
+    let mut impl_items = Vec::new();
+    if let Some(first) = bounded
+        .first()
+        .copied()
+        .or(wrapped.first().map(|(_, _, ty)| ty))
+    {
+        for associated in &associated {
+            impl_items.push(quote!(type #associated = <#first as #path>::#associated;));
+        }
+    }
+
+    for item in &definition.items {
+        let TraitItem::Method(method) = item else {
+            continue;
+        };
+        let Ok(Some(receiver)) = receiver(&method.sig) else {
+            continue;
+        };
+
+        let mut sig = method.sig.clone();
+        rename_enum_generics(&mut sig, generics);
+        let mut args = Vec::new();
+        for (index, arg) in sig.inputs.iter_mut().skip(1).enumerate() {
+            if let FnArg::Typed(arg) = arg {
+                let name = format_ident!("__ace_it_arg{}", index);
+                *arg.pat = parse_quote!(#name);
+                args.push(name);
+            }
+        }
+
+        let method_name = &sig.ident;
+        let (scrutinee, inner) = match receiver {
+            Receiver::Plain => (quote!(self), quote!(__ace_it_inner)),
+            // SAFETY: the enum is pinned, so the value of its variant is too. The enum is only Unpin if the values are,
+            // and can't implement Drop, so nothing moves them out while it's pinned, see `process_pinned`.
+            Receiver::Pinned => (
+                quote!(unsafe { ::core::pin::Pin::get_unchecked_mut(self) }),
+                quote!(unsafe { ::core::pin::Pin::new_unchecked(__ace_it_inner) }),
+            ),
+        };
+        let arms = wrapped.iter().map(|(variant, member, ty)| {
+            let attrs = forwarded_attrs(variant);
+            let variant_name = &variant.ident;
+            let mut call = quote!(<#ty as #path>::#method_name(#inner, #(#args),*));
+            if sig.asyncness.is_some() {
+                call = quote!(#call.await);
+            }
+            if sig.unsafety.is_some() {
+                call = quote!(unsafe { #call });
+            }
+            quote!(#attrs Self::#variant_name { #member: __ace_it_inner, .. } => #call,)
+        });
+        let attrs = method.attrs.iter().filter(|attr| attr.path.is_ident("cfg"));
+        impl_items.push(quote! {
+            #(#attrs)*
+            #[inline]
+            #sig {
+                match #scrutinee {
+                    #(#arms)*
+                }
+            }
+        });
+    }
+
+    // Supertraits of std traits, like `Iterator` of `DoubleEndedIterator`, are implemented for the enum
+    // only if the variants have the same associated types in them too.
+    let mut bounds = vec![(path.clone(), associated.clone())];
+    for supertrait in &definition.supertraits {
+        if let syn::TypeParamBound::Trait(bound) = supertrait {
+            if let Some((path, definition)) = builtin(&bound.path) {
+                bounds.push((path, associated_types(&definition)));
+            }
+        }
+    }
+
+    let mut generics = generics.clone();
+    let where_clause = generics.make_where_clause();
+    for (path, associated) in &bounds {
+        // The other variants have to have the same associated types as the first one.
+        for (index, ty) in bounded.iter().enumerate() {
+            if index == 0 || associated.is_empty() {
+                where_clause.predicates.push(parse_quote!(#ty: #path));
+            } else {
+                let first = bounded[0];
+                where_clause.predicates.push(parse_quote! {
+                    #ty: #path<#(#associated = <#first as #path>::#associated),*>
+                });
+            }
+        }
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let unsafety = &definition.unsafety;
+
+    Ok(quote! {
+        #unsafety impl #impl_generics #path for #enum_name #ty_generics #where_clause {
+            #(#impl_items)*
+        }
+    })
+}
+
+/// Generates the impls keeping the values of the variants pinned, if a delegated method takes `self: Pin<&mut Self>`.
+///
+/// Like pin-project does, the enum is only Unpin if all the values are, which stops it from implementing Unpin
+/// otherwise, and it can't implement Drop, which could move the values out of the pinned enum.
+pub(crate) fn process_pinned<'a>(
+    delegates: &[Delegate],
+    variants: impl Iterator<Item = (&'a Variant, &'a VariantOptions)>,
+    enum_name: &Ident,
+    generics: &Generics,
+) -> Option<TokenStream> {
+    let pinned = |item: &TraitItem| matches!(item, TraitItem::Method(method) if matches!(receiver(&method.sig), Ok(Some(Receiver::Pinned))));
+    let pins = delegates
+        .iter()
+        .filter_map(Delegate::resolved)
+        .any(|(_, definition)| definition.items.iter().any(pinned));
+    if !pins {
+        return None;
+    }
+
+    // The values are held by a struct, as fields can carry the `cfg`s of their variants.
+    let fields = variants
+        .enumerate()
+        .filter_map(|(index, (variant, options))| {
+            let member = wrapped_field(variant, options)?;
+            let ty = member_type(variant, &member)?;
+            let attrs = forwarded_attrs(variant);
+            let field = format_ident!("__ace_it_field{}", index);
+            Some(quote!(#attrs #field: #ty))
+        });
+
+    // The lifetime keeps the bound from being checked until the enum is used, when it has no generics.
+    let mut pinned_generics = generics.clone();
+    pinned_generics
+        .params
+        .insert(0, parse_quote!('__ace_it_pin));
+    let (pinned_impl_generics, pinned_ty_generics, _) = pinned_generics.split_for_impl();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut unpin_where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+    unpin_where_clause
+        .predicates
+        .push(parse_quote!(__AceItPinned #pinned_ty_generics: ::core::marker::Unpin));
+
+    Some(quote! {
+        const _: () = {
+            #[allow(dead_code)]
+            pub struct __AceItPinned #pinned_impl_generics #where_clause {
+                __ace_it_enum: ::core::marker::PhantomData<fn() -> (&'__ace_it_pin (), #enum_name #ty_generics)>,
+                #(#fields,)*
+            }
+
+            impl #pinned_impl_generics ::core::marker::Unpin for #enum_name #ty_generics #unpin_where_clause {}
+
+            trait __AceItMustNotImplDrop {}
+            #[allow(drop_bounds)]
+            impl<T: ::core::ops::Drop> __AceItMustNotImplDrop for T {}
+            impl #impl_generics __AceItMustNotImplDrop for #enum_name #ty_generics #where_clause {}
+        };
+    })
+}
+
+/// Returns the names of the associated types of the trait.
+fn associated_types(definition: &ItemTrait) -> Vec<Ident> {
+    definition
+        .items
+        .iter()
+        .filter_map(|item| match item {
+            TraitItem::Type(ty) => Some(ty.ident.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse2;
+
+    #[test]
+    fn builtin_paths() {
+        let path = |path: TokenStream| builtin(&parse2(path).unwrap()).map(|(path, _)| path);
+        let read = quote!(::std::io::Read).to_string();
+        for named in [quote!(Read), quote!(io::Read), quote!(std::io::Read)] {
+            assert_eq!(path(named).unwrap().to_token_stream().to_string(), read);
+        }
+        assert_eq!(
+            path(quote!(std::iter::Iterator))
+                .unwrap()
+                .to_token_stream()
+                .to_string(),
+            quote!(::core::iter::Iterator).to_string()
+        );
+        assert!(path(quote!(self::Read)).is_none());
+        assert!(path(quote!(fmt::Read)).is_none());
+        assert!(path(quote!(Handler)).is_none());
+    }
+
+    #[test]
+    fn resolves_first_unresolved_trait() {
+        let input = quote! {
+            derive
+            { error, delegate(Read, Handler, Other) }
+            { enum Test { A(u8) } }
+            { trait Handler { fn handle(&self); } }
+        };
+        let resolved: Resolved = parse2(input).unwrap();
+        let expected = quote! {
+            error, delegate(Read, Handler { trait Handler { fn handle(&self); } }, Other)
+        };
+        assert_eq!(resolved.args.to_string(), expected.to_string());
+
+        let options: crate::EnumOptions = parse2(resolved.args.clone()).unwrap();
+        let chain = delegate_chain(
+            &options.delegate,
+            &resolved.mode,
+            &resolved.args,
+            &resolved.item,
+        )
+        .unwrap();
+        assert!(chain
+            .to_string()
+            .starts_with(&format!("{} {{ delegate derive", quote!(Other!))));
+    }
+
+    #[test]
+    fn marked_trait_definition() {
+        let input = quote! {
+            /// Handles requests.
+            pub trait Handler: Send {
+                type Response;
+                const NAME: &'static str = "handler";
+                fn handle(&mut self, request: &str) -> Self::Response;
+                fn new() -> Self where Self: Sized {
+                    unimplemented!()
+                }
+            }
+        };
+        let definition = delegated_definition(&parse2(input).unwrap()).unwrap();
+        let expected = quote! {
+            trait Handler {
+                type Response;
+                fn handle(&mut self, request: &str) -> Self::Response;
+            }
+        };
+        assert_eq!(
+            definition.to_token_stream().to_string(),
+            expected.to_string()
+        );
+    }
+
+    #[test]
+    fn marked_trait_errors() {
+        let input = quote! {
+            trait Handler {
+                fn new() -> u8;
+                fn merge(&mut self, other: Self);
+                fn boxed(self: Box<Self>);
+            }
+        };
+        let result = process_trait(parse2(input).unwrap()).to_string();
+        assert!(result.contains("Can't delegate `fn new`, as it doesn't take `self`"));
+        assert!(result.contains("Can't delegate `fn merge`"));
+        assert!(result.contains("Can't delegate `fn boxed`"));
+        assert!(!result.contains("macro_rules"));
+    }
+
+    #[test]
+    fn renamed_method_generics() {
+        let input = quote! {
+            enum Any<'a, T: Handler> {
+                A(T),
+                B(&'a str),
+            }
+        };
+        let parsed: ItemEnum = parse2(input).unwrap();
+        let delegate: Delegate = parse2(quote! {
+            Handler {
+                trait Handler {
+                    fn handle<'a, T: Debug, U>(&'a self, value: T, other: U) -> Option<&'a T>
+                    where
+                        T: Clone;
+                }
+            }
+        })
+        .unwrap();
+        let options = [VariantOptions::default(), VariantOptions::default()];
+        let result = process_delegate(
+            &delegate,
+            parsed.variants.iter().zip(&options),
+            &parsed.ident,
+            &parsed.generics,
+        )
+        .unwrap()
+        .to_string();
+        let sig = quote! {
+            fn handle<'__ace_it_a, __AceItT: Debug, U>(
+                &'__ace_it_a self,
+                __ace_it_arg0: __AceItT,
+                __ace_it_arg1: U
+            ) -> Option<&'__ace_it_a __AceItT>
+            where
+                __AceItT: Clone
+        };
+        assert!(result.contains(&sig.to_string()));
+    }
+
+    #[test]
+    fn pinned_values() {
+        let input = quote! {
+            enum Test<F> {
+                A(F),
+                #[cfg(unix)]
+                B(u8),
+            }
+        };
+        let parsed: ItemEnum = parse2(input).unwrap();
+        let options = [VariantOptions::default(), VariantOptions::default()];
+        let pinned = |delegates: [TokenStream; 2]| {
+            let delegates: Vec<Delegate> = delegates
+                .into_iter()
+                .map(|path| parse2(path).unwrap())
+                .collect();
+            process_pinned(
+                &delegates,
+                parsed.variants.iter().zip(&options),
+                &parsed.ident,
+                &parsed.generics,
+            )
+            .map(|tokens| tokens.to_string())
+        };
+        assert!(pinned([quote!(Iterator), quote!(Debug)]).is_none());
+
+        let result = pinned([quote!(Debug), quote!(Future)]).unwrap();
+        let fields = quote! {
+            __ace_it_field0: F,
+            #[cfg(unix)]
+            __ace_it_field1: u8,
+        };
+        let unpin = quote! {
+            impl<'__ace_it_pin, F> ::core::marker::Unpin for Test<F>
+            where
+                __AceItPinned<'__ace_it_pin, F>: ::core::marker::Unpin
+            {}
+        };
+        assert!(result.contains(&fields.to_string()));
+        assert!(result.contains(&unpin.to_string()));
+        assert!(result.contains(
+            &quote!(
+                impl<F> __AceItMustNotImplDrop for Test<F> {}
+            )
+            .to_string()
+        ));
+    }
+}
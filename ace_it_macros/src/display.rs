@@ -0,0 +1,174 @@
+//! Generation of the [Display](std::fmt::Display) impl.
+
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Fields, Generics, LitStr, Variant};
+
+use crate::{forwarded_attrs, wrapped_field, VariantOptions};
+
+/// Generates a [Display](std::fmt::Display) impl for the enum.
+///
+/// Variants with a format string are written with it, with their fields bound to their names,
+/// or to `_0`, `_1` and so on for unnamed ones.
+/// Variants without one display their wrapped value, unit variants display their name.
+pub(crate) fn process_display<'a>(
+    variants: impl Iterator<Item = (&'a Variant, &'a VariantOptions)>,
+    enum_name: &Ident,
+    generics: &Generics,
+) -> syn::Result<TokenStream> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms = variants
+        .map(|(variant, options)| {
+            let attrs = forwarded_attrs(variant);
+            display_arm(variant, options).map(|arm| quote!(#attrs #arm))
+        })
+        .collect::<syn::Result<Vec<_>>>()?;
+    let scrutinee = if arms.is_empty() {
+        quote!(*self)
+    } else {
+        quote!(self)
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::core::fmt::Display for #enum_name #ty_generics #where_clause {
+            #[allow(unused_variables)]
+            fn fmt(&self, __formatter: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                match #scrutinee {
+                    #(#arms)*
+                }
+            }
+        }
+    })
+}
+
+/// Generates the match arm displaying the variant.
+fn display_arm(variant: &Variant, options: &VariantOptions) -> syn::Result<TokenStream> {
+    let variant_name = &variant.ident;
+
+    if let Some(format) = &options.display {
+        let format = LitStr::new(&rewrite_positional(&format.value()), format.span());
+        let pattern = match &variant.fields {
+            Fields::Named(fields) => {
+                let names = fields.named.iter().map(|field| &field.ident);
+                quote!(Self::#variant_name { #(#names),* })
+            }
+            Fields::Unnamed(fields) => {
+                let names = (0..fields.unnamed.len())
+                    .map(|i| format_ident!("_{}", i, span = format.span()));
+                quote!(Self::#variant_name(#(#names),*))
+            }
+            Fields::Unit => quote!(Self::#variant_name),
+        };
+        return Ok(quote! {
+            #pattern => ::core::write!(__formatter, #format),
+        });
+    }
+
+    if let Some(member) = wrapped_field(variant, options) {
+        return Ok(quote! {
+            Self::#variant_name { #member: value, .. } => ::core::fmt::Display::fmt(value, __formatter),
+        });
+    }
+
+    if let Fields::Unit = variant.fields {
+        let name = variant_name.to_string();
+        return Ok(quote! {
+            Self::#variant_name => __formatter.write_str(#name),
+        });
+    }
+
+    Err(syn::Error::new_spanned(
+        variant,
+        "Variant doesn't wrap a single value, so it needs a format string: `#[ace_it(display = \"...\")]`",
+    ))
+}
+
+/// Rewrites positional arguments of a format string into the names the unnamed fields are bound to.
+///
+/// `{0}` becomes `{_0}`, `{}` becomes the next unnamed field and `1$` in a format spec becomes `_1$`,
+/// so the fields can be captured from the scope.
+fn rewrite_positional(format: &str) -> String {
+    let mut result = String::with_capacity(format.len());
+    let mut chars = format.chars().peekable();
+    let mut next_implicit = 0;
+
+    while let Some(c) = chars.next() {
+        result.push(c);
+        match c {
+            '{' if chars.peek() == Some(&'{') => result.push(chars.next().unwrap()),
+            '{' => {
+                let mut placeholder = String::new();
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                    placeholder.push(c);
+                }
+
+                let (argument, spec) = match placeholder.find(':') {
+                    Some(index) => placeholder.split_at(index),
+                    None => (placeholder.as_str(), ""),
+                };
+                if argument.is_empty() {
+                    result.push_str(&format!("_{}", next_implicit));
+                    next_implicit += 1;
+                } else if argument.bytes().all(|b| b.is_ascii_digit()) {
+                    result.push('_');
+                    result.push_str(argument);
+                } else {
+                    result.push_str(argument);
+                }
+                result.push_str(&rewrite_spec(spec));
+                result.push('}');
+            }
+            _ => {}
+        }
+    }
+
+    result
+}
+
+/// Rewrites the `N$` width and precision arguments of a format spec into `_N$`.
+fn rewrite_spec(spec: &str) -> String {
+    let mut result = String::with_capacity(spec.len());
+    let mut digits = String::new();
+
+    for c in spec.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        if c == '$' && !digits.is_empty() {
+            result.push('_');
+        }
+        result.push_str(&digits);
+        digits.clear();
+        result.push(c);
+    }
+    result.push_str(&digits);
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positional_arguments() {
+        assert_eq!(rewrite_positional("{0}: {1:?}"), "{_0}: {_1:?}");
+        assert_eq!(rewrite_positional("{} and {}"), "{_0} and {_1}");
+        assert_eq!(rewrite_positional("{0:>1$.2$}"), "{_0:>_1$._2$}");
+        assert_eq!(rewrite_positional("{0:08}"), "{_0:08}");
+    }
+
+    #[test]
+    fn named_arguments_and_escapes() {
+        assert_eq!(
+            rewrite_positional("{line}:{column:<4}"),
+            "{line}:{column:<4}"
+        );
+        assert_eq!(rewrite_positional("{{0}} }}"), "{{0}} }}");
+    }
+}
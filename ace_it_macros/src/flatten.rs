@@ -0,0 +1,254 @@
+//! Flattening of variants that wrap other ace_it enums.
+//!
+//! An enum marked with `#[ace_it(export)]` gets a `macro_rules!` macro named like the enum,
+//! which passes the types the enum is converted from on to `__ace_it_flatten`,
+//! or to `__ace_it_into` along with the patterns of its variants.
+//! An enum with a `#[ace_it(flatten)]` variant isn't expanded right away, it calls the macro of the enum
+//! the variant wraps instead, which calls back with the types to replace `flatten` with `from(...)`.
+
+use proc_macro2::{Delimiter, Group, Ident, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Fields, Generics, ItemEnum, Token, Type, Variant,
+};
+
+use crate::{into, Conversion, Source};
+
+/// Generates the macro that passes the types the enum is converted from to `__ace_it_flatten` and `__ace_it_into`.
+pub(crate) fn process_export(
+    conversions: &[Conversion],
+    enum_name: &Ident,
+    generics: &Generics,
+) -> syn::Result<TokenStream> {
+    if !generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            generics,
+            "`#[ace_it(export)]` doesn't support generic enums",
+        ));
+    }
+
+    let macro_name = format_ident!("__ace_it_{}", enum_name);
+    let sources: Vec<_> = conversions
+        .iter()
+        .map(|conversion| {
+            let source = &conversion.source;
+            let attrs = conversion.attrs();
+            quote!(#attrs #source)
+        })
+        .collect();
+    let variants = into::export_variants(conversions);
+    Ok(quote! {
+        #[allow(unused_macros)]
+        macro_rules! #macro_name {
+            (flatten $mode:ident { $($args:tt)* } { $($item:tt)* }) => {
+                ::ace_it::__ace_it_flatten! { $mode { $($args)* } { $($item)* } [#(#sources),*] }
+            };
+            (into $mode:ident { $($args:tt)* } { $($item:tt)* }) => {
+                ::ace_it::__ace_it_into! { $mode { $($args)* } { $($item)* } [#(#sources),*] [#variants] }
+            };
+        }
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #macro_name as #enum_name;
+    })
+}
+
+/// Returns the call to the macro of the enum wrapped by the first `#[ace_it(flatten)]` variant,
+/// or [None] if there are no such variants left.
+pub(crate) fn flatten_chain(
+    mode: &Ident,
+    args: &TokenStream,
+    item: &ItemEnum,
+) -> Option<syn::Result<TokenStream>> {
+    let (variant, _) = item
+        .variants
+        .iter()
+        .find_map(|variant| flatten_option(variant).map(|flatten| (variant, flatten)))?;
+
+    Some(wrapped_enum_path(variant).map(|path| {
+        quote! {
+            #path! { flatten #mode { #args } { #item } }
+        }
+    }))
+}
+
+/// Returns the path of the macro of the enum the variant wraps, without generic arguments.
+fn wrapped_enum_path(variant: &Variant) -> syn::Result<syn::Path> {
+    let ty = match &variant.fields {
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => &fields.unnamed[0].ty,
+        _ => {
+            return Err(syn::Error::new(
+                variant.ident.span(),
+                "`#[ace_it(flatten)]` needs the variant to wrap a single value",
+            ))
+        }
+    };
+
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let mut path = path.path.clone();
+            if let Some(segment) = path.segments.last_mut() {
+                segment.arguments = syn::PathArguments::None;
+            }
+            Ok(path)
+        }
+        _ => Err(syn::Error::new_spanned(
+            ty,
+            "`#[ace_it(flatten)]` needs the variant to wrap an enum marked with `#[ace_it(export)]`",
+        )),
+    }
+}
+
+/// Returns the `flatten` option of the variant, if it has one.
+fn flatten_option(variant: &Variant) -> Option<Ident> {
+    variant.attrs.iter().find_map(|attr| {
+        let tokens = option_tokens(attr)?;
+        option_index(&tokens).map(|index| match &tokens[index] {
+            TokenTree::Ident(ident) => ident.clone(),
+            _ => unreachable!(),
+        })
+    })
+}
+
+/// Returns the tokens of the option list of an `#[ace_it(...)]` attribute.
+fn option_tokens(attr: &Attribute) -> Option<Vec<TokenTree>> {
+    if !attr.path.is_ident("ace_it") {
+        return None;
+    }
+    match attr.tokens.clone().into_iter().next()? {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => {
+            Some(group.stream().into_iter().collect())
+        }
+        _ => None,
+    }
+}
+
+/// Returns the index of a standalone `flatten` among the tokens of an option list.
+fn option_index(tokens: &[TokenTree]) -> Option<usize> {
+    let is_comma = |token: Option<&TokenTree>| {
+        token.is_none_or(|token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == ','))
+    };
+    (0..tokens.len()).find(|&i| {
+        matches!(&tokens[i], TokenTree::Ident(ident) if ident == "flatten")
+            && (i == 0 || is_comma(tokens.get(i - 1)))
+            && is_comma(tokens.get(i + 1))
+    })
+}
+
+/// The input of `__ace_it_flatten`: how the enum is expanded, the options of the enum, the enum itself
+/// and the types the enum wrapped by its first `#[ace_it(flatten)]` variant is converted from.
+pub(crate) struct Flattened {
+    pub(crate) mode: Ident,
+    pub(crate) args: TokenStream,
+    pub(crate) item: ItemEnum,
+}
+
+impl Parse for Flattened {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mode = input.parse()?;
+        let args;
+        syn::braced!(args in input);
+        let args: TokenStream = args.parse()?;
+        let item;
+        syn::braced!(item in input);
+        let mut item: ItemEnum = item.parse()?;
+        let types;
+        syn::bracketed!(types in input);
+        let types = Punctuated::<Source, Token![,]>::parse_terminated(&types)?;
+
+        let flattened = item
+            .variants
+            .iter_mut()
+            .find_map(|variant| flatten_option(variant).map(|_| variant));
+        if let Some(variant) = flattened {
+            replace_flatten(variant, &types);
+        }
+
+        Ok(Self { mode, args, item })
+    }
+}
+
+/// Replaces the `flatten` option of the variant with `from(...)` listing the given types.
+///
+/// The types are spanned at `flatten`, so they're resolved and reported where the variant is.
+fn replace_flatten(variant: &mut Variant, types: &Punctuated<Source, Token![,]>) {
+    for attr in &mut variant.attrs {
+        let Some(mut tokens) = option_tokens(attr) else {
+            continue;
+        };
+        let Some(index) = option_index(&tokens) else {
+            continue;
+        };
+
+        let span = tokens[index].span();
+        let from = respan(quote!(from(#types)), span);
+        tokens.splice(index..=index, from);
+        let mut group = Group::new(Delimiter::Parenthesis, tokens.into_iter().collect());
+        group.set_span(attr.tokens.span());
+        attr.tokens = TokenTree::Group(group).into();
+        return;
+    }
+}
+
+/// Sets the span of every token in the stream.
+fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            if let TokenTree::Group(group) = &token {
+                let mut respanned = Group::new(group.delimiter(), respan(group.stream(), span));
+                respanned.set_span(span);
+                token = TokenTree::Group(respanned);
+            } else {
+                token.set_span(span);
+            }
+            token
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse2;
+
+    #[test]
+    fn replaces_flatten_with_sources() {
+        let input = quote! {
+            attribute
+            { error }
+            {
+                enum Test {
+                    #[ace_it(primary, flatten)]
+                    A(Inner),
+                    #[ace_it(flatten)]
+                    B(other::Inner),
+                }
+            }
+            [u8, String]
+        };
+        let flattened: Flattened = parse2(input).unwrap();
+        let variants = &flattened.item.variants;
+        assert_eq!(
+            variants[0].attrs[0].tokens.to_string(),
+            quote!((primary, from(u8, String))).to_string()
+        );
+        let chain = flatten_chain(&flattened.mode, &flattened.args, &flattened.item)
+            .unwrap()
+            .unwrap();
+        assert!(chain
+            .to_string()
+            .starts_with("other :: Inner ! { flatten attribute { error }"));
+    }
+
+    #[test]
+    fn ignores_other_flatten_tokens() {
+        let tokens: Vec<_> = quote!(with = flatten::convert, from = u8)
+            .into_iter()
+            .collect();
+        assert_eq!(option_index(&tokens), None);
+    }
+}
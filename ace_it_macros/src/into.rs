@@ -0,0 +1,218 @@
+//! Conversion of the enum into a superset enum, set with `#[ace_it(into = Target)]`.
+//!
+//! The target has to be marked with `#[ace_it(export)]`. The enum isn't expanded right away,
+//! it calls the macro of the target instead, which calls back `__ace_it_into` with the types the target
+//! is converted from and the patterns of its variants.
+
+use std::collections::HashSet;
+
+use proc_macro2::{Ident, TokenStream};
+use quote::quote;
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    Generics, ItemEnum, Path, Token, Type, Variant,
+};
+
+use crate::{normalize, Conversion, Errors, Source, Via};
+
+/// What the macro of the target passes back about it.
+pub(crate) struct Target {
+    /// The types the target is converted from.
+    sources: Vec<Type>,
+    /// The variants of the target that wrap a value of a type it's converted from,
+    /// as the type with the attributes of the variant, the pattern matching the variant
+    /// and the expression of the value it binds.
+    ///
+    /// The patterns name the target `__AceItTarget`.
+    variants: Vec<(Source, TokenStream, TokenStream)>,
+}
+
+impl Parse for Target {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let sources;
+        syn::bracketed!(sources in input);
+        let sources = Punctuated::<Source, Token![,]>::parse_terminated(&sources)?;
+
+        let variants;
+        syn::bracketed!(variants in input);
+        let mut parsed = Vec::new();
+        while !variants.is_empty() {
+            let (source, pattern, value);
+            syn::braced!(source in variants);
+            syn::braced!(pattern in variants);
+            syn::braced!(value in variants);
+            parsed.push((source.parse()?, pattern.parse()?, value.parse()?));
+            if !variants.is_empty() {
+                variants.parse::<Token![,]>()?;
+            }
+        }
+
+        Ok(Self {
+            sources: sources.into_iter().map(|source| source.ty).collect(),
+            variants: parsed,
+        })
+    }
+}
+
+/// The input of `__ace_it_into`: how the enum is expanded, the options of the enum, the enum itself
+/// and what's passed back about the target.
+pub(crate) struct Resolved {
+    pub(crate) mode: Ident,
+    pub(crate) args: TokenStream,
+    pub(crate) item: ItemEnum,
+    pub(crate) target: Target,
+}
+
+impl Parse for Resolved {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mode = input.parse()?;
+        let args;
+        syn::braced!(args in input);
+        let item;
+        syn::braced!(item in input);
+        Ok(Self {
+            mode,
+            args: args.parse()?,
+            item: item.parse()?,
+            target: input.parse()?,
+        })
+    }
+}
+
+/// Generates the entries of the variants of an exported enum, passed back to `__ace_it_into`.
+pub(crate) fn export_variants(conversions: &[Conversion]) -> TokenStream {
+    let variants = conversions
+        .iter()
+        .filter(|conversion| matches!(conversion.via, Via::Direct))
+        .map(|conversion| {
+            let source = &conversion.source;
+            let attrs = conversion.attrs();
+            let (pattern, value) = conversion.destructure(&quote!(__AceItTarget));
+            quote!({ #attrs #source } { #pattern } { #value })
+        });
+    quote!(#(#variants),*)
+}
+
+/// Returns the call to the macro of the target.
+pub(crate) fn into_chain(
+    path: &Path,
+    mode: &Ident,
+    args: &TokenStream,
+    item: &ItemEnum,
+) -> TokenStream {
+    quote! {
+        #path! { into #mode { #args } { #item } }
+    }
+}
+
+/// Generates the From impl converting the enum into the target, and the TryFrom impl taking it back out.
+///
+/// Every variant has to wrap a type the target is converted from.
+pub(crate) fn process_into<'a>(
+    conversions: &[Conversion],
+    variants: impl Iterator<Item = &'a Variant>,
+    enum_name: &Ident,
+    generics: &Generics,
+    path: &Path,
+    target: &Target,
+) -> syn::Result<TokenStream> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let self_ty: Type = syn::parse_quote!(#enum_name #ty_generics);
+    let target_ty: Type = syn::parse_quote!(#path);
+    let key =
+        |ty: &Type, self_ty: &Type| normalize::type_key(&normalize::normalize_type(ty, self_ty));
+
+    let direct: Vec<_> = conversions
+        .iter()
+        .filter(|conversion| matches!(conversion.via, Via::Direct))
+        .collect();
+
+    let mut errors = Errors::default();
+    for variant in variants {
+        if !direct
+            .iter()
+            .any(|conversion| conversion.variant.ident == variant.ident)
+        {
+            errors.push(syn::Error::new(
+                variant.ident.span(),
+                format!(
+                    "Variant `{}` can't be converted into `{}`, it isn't converted from a value it wraps",
+                    variant.ident,
+                    normalize::type_name(&target_ty),
+                ),
+            ));
+        }
+    }
+
+    let known: HashSet<_> = target
+        .sources
+        .iter()
+        .map(|source| key(source, &target_ty))
+        .collect();
+    let missing: Vec<_> = direct
+        .iter()
+        .filter(|conversion| !known.contains(&key(&conversion.source, &self_ty)))
+        .map(|conversion| format!("`{}`", normalize::type_name(&conversion.source)))
+        .collect();
+    if !missing.is_empty() {
+        errors.push(syn::Error::new_spanned(
+            path,
+            format!(
+                "`{}` isn't converted from {}",
+                normalize::type_name(&target_ty),
+                missing.join(", "),
+            ),
+        ));
+    }
+
+    if let Errors(Some(error)) = errors {
+        return Err(error);
+    }
+
+    let from_arms = direct.iter().map(|conversion| {
+        let source = &conversion.source;
+        let (pattern, value) = conversion.destructure(&quote!(#enum_name));
+        let attrs = conversion.attrs();
+        quote!(#attrs #pattern => <Self as ::core::convert::From<#source>>::from(#value),)
+    });
+
+    let sources: Vec<_> = direct
+        .iter()
+        .map(|conversion| (key(&conversion.source, &self_ty), conversion))
+        .collect();
+    let try_from_arms = target.variants.iter().filter_map(|(target_source, pattern, value)| {
+        let key = key(&target_source.ty, &target_ty);
+        let (_, conversion) = sources.iter().find(|(source, _)| *source == key)?;
+        let source = &conversion.source;
+        let target_attrs = &target_source.attrs;
+        let attrs = conversion.attrs();
+        Some(quote! {
+            #(#target_attrs)* #attrs
+            #pattern => ::core::result::Result::Ok(<Self as ::core::convert::From<#source>>::from(#value)),
+        })
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::core::convert::From<#enum_name #ty_generics> for #path #where_clause {
+            fn from(value: #enum_name #ty_generics) -> Self {
+                match value {
+                    #(#from_arms)*
+                }
+            }
+        }
+
+        impl #impl_generics ::core::convert::TryFrom<#path> for #enum_name #ty_generics #where_clause {
+            type Error = #path;
+
+            fn try_from(value: #path) -> ::core::result::Result<Self, Self::Error> {
+                type __AceItTarget = #path;
+                match value {
+                    #(#try_from_arms)*
+                    #[allow(unreachable_patterns)]
+                    other => ::core::result::Result::Err(other),
+                }
+            }
+        }
+    })
+}
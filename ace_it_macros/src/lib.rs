@@ -0,0 +1,3435 @@
+//! Proc macros of [ace_it](https://docs.rs/ace_it), which re-exports them along with the traits they implement.
+
+mod accessors;
+mod ace_enum;
+mod auto_enum;
+mod delegate;
+mod display;
+mod flatten;
+mod into;
+mod newtype;
+mod normalize;
+
+use std::collections::{hash_map::Entry, HashMap};
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    punctuated::Punctuated,
+    spanned::Spanned,
+    Attribute, Expr, Fields, FieldsUnnamed, GenericArgument, Generics, LitStr, Member, Meta,
+    NestedMeta, PathArguments, Token, Type, Variant,
+};
+
+/// Generates [From] impls for the given enum.
+/// ## Usage
+/// ### Applying the macro
+/// This will generate the From impls for each type and turns it into an accompanying variant.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error {
+///   Io(std::io::Error),
+///   ParseInt(std::num::ParseIntError),
+///   ParseFloat(std::num::ParseFloatError),
+/// }
+/// ```
+/// ### Compile errors
+/// This will error because there are two variants with the same type.
+/// There is no way to know which one to use.
+/// ```compile_fail
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum SomeEnum {
+///     A(i32),
+///     B(i32) // Duplicate i32, shouldn't compile
+/// }
+/// ```
+///
+/// Types are compared after resolving what can be resolved without seeing the `use` items,
+/// so `std::io::Error` and `io::Error`, or `Vec<u8>` and `alloc::vec::Vec<u8>`, count as the same type.
+/// ```compile_fail
+/// # #[macro_use] extern crate ace_it;
+/// use std::io;
+///
+/// #[ace_it]
+/// enum SomeEnum {
+///     A(std::io::Error),
+///     B(io::Error) // Same as std::io::Error, shouldn't compile
+/// }
+/// ```
+/// Types that might still be the same, like `Error` and `io::Error`, get a warning,
+/// which `#[allow(deprecated)]` on the enum or the variant silences.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #![deny(warnings)]
+/// use std::fmt::Error;
+///
+/// #[allow(deprecated)]
+/// #[ace_it]
+/// enum SomeEnum {
+///     A(std::io::Error),
+///     B(Error) // Not the same as std::io::Error
+/// }
+/// ```
+/// ### Multiple fields
+/// Variants with multiple unnamed fields are converted from a tuple of their fields.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error {
+///     Message(String),
+///     Span(usize, usize),
+/// }
+///
+/// let error: Error = (4, 2).into();
+/// assert!(matches!(error, Error::Span(4, 2)));
+/// ```
+/// A tuple counts as a type of its own, so it can't be wrapped by another variant.
+/// ```compile_fail
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error {
+///     Span(usize, usize),
+///     Range((usize, usize)), // Same as Span's tuple, shouldn't compile
+/// }
+/// ```
+/// ### Marking the converted field
+/// A variant with named fields, or with unnamed fields that shouldn't be converted from a tuple,
+/// can mark one of its fields with `#[from]` or `#[source]`.
+/// The From impl converts from the type of that field and fills the rest of the fields with [Default::default].
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// use std::path::PathBuf;
+///
+/// #[ace_it]
+/// enum Error {
+///     Io {
+///         #[source]
+///         source: std::io::Error,
+///         path: Option<PathBuf>,
+///     },
+///     ParseInt(#[from] std::num::ParseIntError, &'static str),
+/// }
+///
+/// let error: Error = std::io::Error::from(std::io::ErrorKind::NotFound).into();
+/// assert!(matches!(error, Error::Io { path: None, .. }));
+/// ```
+/// The rest of the fields have to implement [Default].
+/// ```compile_fail
+/// # #[macro_use] extern crate ace_it;
+/// struct NoDefault;
+///
+/// #[ace_it]
+/// enum Error {
+///     Io {
+///         #[from]
+///         source: std::io::Error,
+///         context: NoDefault, // Can't be defaulted, shouldn't compile
+///     },
+/// }
+/// ```
+/// ### Generic enums
+/// Generics, lifetimes, const generics and the where clause of the enum are carried over to the From impls.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error<'a, E: std::error::Error, const N: usize> {
+///     Parse(&'a str),
+///     Other(Box<E>),
+///     Bytes([u8; N]),
+/// }
+///
+/// let error: Error<'_, std::fmt::Error, 2> = "unexpected token".into();
+/// assert!(matches!(error, Error::Parse("unexpected token")));
+/// ```
+///
+/// A variant can wrap a bare type parameter, like `Option<T>` is converted from `T`.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Single<T> {
+///     Value(T),
+/// }
+///
+/// assert!(matches!(Single::from(1), Single::Value(1)));
+/// ```
+/// But then it can't be converted from any other type that doesn't contain the parameter,
+/// as `impl<T> From<T> for Error<T>` would overlap with `impl<T> From<std::io::Error> for Error<T>`
+/// when `T` is `std::io::Error`. One of the variants has to be skipped.
+/// ```compile_fail
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error<T> {
+///     Io(std::io::Error),
+///     Other(T), // Needs #[ace_it(skip)]
+/// }
+/// ```
+/// ### Skipping a variant
+/// A variant marked with `#[ace_it(skip)]` doesn't get a From impl and isn't checked for duplicates.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error {
+///     Message(String),
+///     #[ace_it(skip)]
+///     Context(String), // Same type as Message, but skipped
+/// }
+///
+/// let error: Error = String::from("oops").into();
+/// assert!(matches!(error, Error::Message(_)));
+/// ```
+/// ### Primary variants
+/// Out of the variants that wrap the same type, one can be marked with `#[ace_it(primary)]`.
+/// The From impl converts to it, the rest of them stay as plain variants.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error {
+///     #[ace_it(primary)]
+///     Message(String),
+///     Context(String),
+///     Hint(String),
+/// }
+///
+/// let error: Error = String::from("oops").into();
+/// assert!(matches!(error, Error::Message(_)));
+/// ```
+/// ### TryFrom impls
+/// With `#[ace_it(try_from)]` on the enum, every From impl gets an accompanying TryFrom impl
+/// that takes the value back out of the enum, giving the enum back if it's a different variant.
+/// Variants that hold the converted value as is can also be borrowed from with `TryFrom<&Enum>`.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// use std::convert::TryFrom;
+///
+/// #[derive(Debug)]
+/// #[ace_it(try_from)]
+/// enum Error {
+///     Io(std::io::Error),
+///     ParseInt(std::num::ParseIntError),
+/// }
+///
+/// let error: Error = "x".parse::<i32>().unwrap_err().into();
+/// assert!(<&std::io::Error>::try_from(&error).is_err());
+/// assert!(<&std::num::ParseIntError>::try_from(&error).is_ok());
+/// assert!(std::num::ParseIntError::try_from(error).is_ok());
+/// ```
+/// Types that are a type parameter of the enum, or one behind a reference or a [Box], don't get TryFrom impls,
+/// as Rust only allows implementing foreign traits like TryFrom for them in the crate that defines them.
+/// ### Error impl
+/// With `#[ace_it(error)]` on the enum, it gets an [std::error::Error] impl.
+/// Its `source` returns the wrapped value of the variant if it's an error,
+/// and [None] for unit variants and variants that wrap something else.
+/// The enum still needs [Debug] and [Display](std::fmt::Display) impls.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// use std::error::Error as _;
+///
+/// #[derive(Debug)]
+/// #[ace_it(error)]
+/// enum Error {
+///     Io(std::io::Error),
+///     Message(String),
+///     Timeout,
+/// }
+///
+/// impl std::fmt::Display for Error {
+///     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+///         f.write_str("something failed")
+///     }
+/// }
+///
+/// let io = Error::from(std::io::Error::from(std::io::ErrorKind::NotFound));
+/// assert!(io.source().is_some());
+/// assert!(Error::from(String::from("oops")).source().is_none());
+/// assert!(Error::Timeout.source().is_none());
+/// ```
+/// Only `'static` errors can be returned from `source`, so it returns [None] for types naming a lifetime other than `'static`.
+/// Boxed error trait objects, like `Box<dyn std::error::Error + Send + Sync>`, are returned too,
+/// as long as the field is written as a [Box] of `dyn` with a trait named `Error`.
+/// ### Display impl
+/// With `#[ace_it(display)]` on the enum, it gets a [Display](std::fmt::Display) impl.
+/// Variants can set their format string with `#[ace_it(display = "...")]`,
+/// referring to unnamed fields by their index and to named fields by their name.
+/// Variants without one display their wrapped value as is, unit variants display their name.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug)]
+/// #[ace_it(display, error)]
+/// enum Error {
+///     #[ace_it(display = "I/O failed: {0}")]
+///     Io(std::io::Error),
+///     ParseInt(std::num::ParseIntError),
+///     #[ace_it(display = "{line}:{column}: unexpected token")]
+///     Syntax { line: usize, column: usize },
+///     Timeout,
+/// }
+///
+/// let error = Error::from("x".parse::<i32>().unwrap_err());
+/// assert_eq!(error.to_string(), "invalid digit found in string");
+/// assert_eq!(Error::Syntax { line: 4, column: 2 }.to_string(), "4:2: unexpected token");
+/// assert_eq!(Error::Timeout.to_string(), "Timeout");
+/// ```
+/// Variants with multiple fields and no wrapped value need a format string.
+/// ```compile_fail
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it(display)]
+/// enum Error {
+///     Span(usize, usize), // No format string, shouldn't compile
+/// }
+/// ```
+/// ### Accessors
+/// With `#[ace_it(accessors)]` on the enum, every variant gets an `is_*` method named after it in snake case.
+/// Variants with unnamed fields also get `as_*`, `as_*_mut` and `into_*` methods,
+/// which return a tuple if there are multiple fields.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug)]
+/// #[ace_it(accessors)]
+/// enum Error {
+///     Io(std::io::Error),
+///     ParseInt(std::num::ParseIntError),
+///     Span(usize, usize),
+///     Timeout,
+/// }
+///
+/// let mut error = Error::from((4, 2));
+/// assert!(error.is_span());
+/// assert!(error.as_io().is_none());
+/// if let Some((start, _)) = error.as_span_mut() {
+///     *start = 3;
+/// }
+/// assert_eq!(error.into_span().unwrap(), (3, 2));
+/// assert!(Error::Timeout.is_timeout());
+/// ```
+/// ### Boxed values
+/// A variant that wraps a [Box], [Arc](std::sync::Arc) or [Rc](std::rc::Rc) can be marked with `#[ace_it(boxed)]`
+/// to also be converted from the value behind the pointer.
+/// With `#[ace_it(boxed)]` on the enum, the value of every variant is put in a [Box],
+/// and every variant is converted from both the boxed and the unboxed value.
+/// Values already behind a pointer aren't boxed again, and pointers to unsized types, like `Box<dyn Error>`,
+/// are only converted from the pointer.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug)]
+/// struct DbError([u8; 256]);
+///
+/// #[ace_it]
+/// enum Error {
+///     #[ace_it(boxed)]
+///     Db(Box<DbError>),
+///     Io(std::io::Error),
+/// }
+///
+/// #[ace_it(boxed)]
+/// enum BoxedError {
+///     Db(DbError), // Becomes Db(Box<DbError>)
+///     Io(std::io::Error),
+/// }
+///
+/// fn query() -> Result<(), DbError> {
+///     Err(DbError([0; 256]))
+/// }
+///
+/// fn run() -> Result<(), Error> {
+///     Ok(query()?)
+/// }
+///
+/// fn run_boxed() -> Result<(), BoxedError> {
+///     Ok(query()?)
+/// }
+///
+/// assert!(matches!(run(), Err(Error::Db(_))));
+/// assert!(matches!(run_boxed(), Err(BoxedError::Db(_))));
+/// assert!(std::mem::size_of::<BoxedError>() < std::mem::size_of::<DbError>());
+/// ```
+/// ### Additional source types
+/// A variant that wraps a single value can list more types it's converted from with `#[ace_it(from(...))]`.
+/// They are converted into the wrapped value with [Into], and are checked for duplicates like the wrapped types.
+/// Lifetimes can be elided with `'_`.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// use std::borrow::Cow;
+/// use std::path::{Path, PathBuf};
+///
+/// #[ace_it]
+/// enum Error {
+///     #[ace_it(from(&str, Cow<'_, str>))]
+///     Message(String),
+///     #[ace_it(from(&Path))]
+///     Path(PathBuf),
+/// }
+///
+/// fn check(path: &Path) -> Result<(), Error> {
+///     if path.is_absolute() {
+///         return Err("absolute paths aren't supported".into());
+///     }
+///     Err(path.into())
+/// }
+///
+/// assert!(matches!(check(Path::new("/")), Err(Error::Message(_))));
+/// assert!(matches!(check(Path::new("a")), Err(Error::Path(_))));
+/// ```
+/// ### Conversion functions
+/// A variant can be converted with a function, or a closure, set with `#[ace_it(with = ...)]`.
+/// The type it takes is set with `from = Type`, or with `from(...)` for multiple types.
+/// It returns either the value the variant wraps or the whole enum.
+/// The variant isn't converted from the value it wraps then.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug)]
+/// struct HttpError {
+///     status: u16,
+/// }
+///
+/// #[derive(Debug)]
+/// struct ClientError(u16);
+///
+/// fn from_client(error: ClientError) -> HttpError {
+///     HttpError { status: error.0 }
+/// }
+///
+/// #[derive(Debug)]
+/// #[ace_it]
+/// enum Error {
+///     #[ace_it(with = from_client, from = ClientError)]
+///     Http(HttpError),
+///     #[ace_it(with = |code: i32| if code < 0 { Error::Signal } else { Error::Exit(code) }, from = i32)]
+///     Exit(i32),
+///     Signal,
+/// }
+///
+/// assert!(matches!(Error::from(ClientError(404)), Error::Http(HttpError { status: 404 })));
+/// assert!(matches!(Error::from(-9), Error::Signal));
+/// assert!(matches!(Error::from(1), Error::Exit(1)));
+/// ```
+/// A variant with named fields and none of them marked with `#[from]` can be converted with a function returning the enum.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it]
+/// enum Error {
+///     #[ace_it(with = |code: u16| Error::Http { code, retry: code == 503 }, from = u16)]
+///     Http { code: u16, retry: bool },
+/// }
+///
+/// assert!(matches!(Error::from(503), Error::Http { code: 503, retry: true }));
+/// ```
+/// ### Unit variants
+/// A unit variant can be converted from a type that carries no information worth keeping,
+/// set with `#[ace_it(from = Type)]` or `#[ace_it(from(...))]`.
+/// The value is dropped and the unit variant is returned.
+/// These types are checked for duplicates like any other.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug)]
+/// #[ace_it]
+/// enum Error {
+///     Io(std::io::Error),
+///     #[ace_it(from = std::fmt::Error)]
+///     Fmt,
+/// }
+///
+/// fn write_name(name: &str) -> Result<String, Error> {
+///     use std::fmt::Write;
+///     let mut out = String::new();
+///     write!(out, "{name}")?;
+///     Ok(out)
+/// }
+///
+/// assert!(matches!(Error::from(std::fmt::Error), Error::Fmt));
+/// assert_eq!(write_name("ace").unwrap(), "ace");
+/// ```
+/// ### Flattening nested enums
+/// A variant that wraps another ace_it enum can be marked with `#[ace_it(flatten)]`
+/// to also be converted from every type the inner enum is converted from, through the inner enum.
+/// The inner enum has to be marked with `#[ace_it(export)]`, which generates a macro named like the enum
+/// that passes on the types it's converted from, so the inner enum can't be generic
+/// and has to be in the same crate as the outer one.
+/// The flattened types are checked for duplicates like the wrapped types.
+/// They're spelled like in the inner enum, so they have to be in scope where the outer enum is.
+/// Without `#[ace_it(export)]` on the inner enum, the variant fails with "cannot find macro",
+/// naming the inner enum. The outer enum is still declared, only its impls are missing.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// mod parse {
+///     #[derive(Debug)]
+///     #[ace_it(export)]
+///     pub enum ParseError {
+///         Int(std::num::ParseIntError),
+///         Float(std::num::ParseFloatError),
+///     }
+/// }
+///
+/// use parse::ParseError;
+///
+/// #[derive(Debug)]
+/// #[ace_it]
+/// enum AppError {
+///     #[ace_it(flatten)]
+///     Parse(ParseError),
+///     Io(std::io::Error),
+/// }
+///
+/// fn parse(input: &str) -> Result<i32, AppError> {
+///     Ok(input.parse()?)
+/// }
+///
+/// # fn main() {
+/// assert!(matches!(parse("ace"), Err(AppError::Parse(ParseError::Int(_)))));
+/// # }
+/// ```
+/// ### Converting into a superset enum
+/// An enum can be converted into another one that wraps all of the same types,
+/// set with `#[ace_it(into = Target)]`. Each variant is converted through the type it wraps,
+/// and a TryFrom impl takes the enum back out of the target, returning the target if it wraps another type.
+/// The target has to be marked with `#[ace_it(export)]`, like for [flattening](#flattening-nested-enums),
+/// and fails the same way without it.
+/// Every variant has to wrap a type the target is converted from, the missing types are reported otherwise.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// # use std::convert::TryFrom;
+/// #[derive(Debug)]
+/// #[ace_it(export)]
+/// enum AppError {
+///     Io(std::io::Error),
+///     Utf8(std::str::Utf8Error),
+///     Config(String),
+/// }
+///
+/// #[derive(Debug)]
+/// #[ace_it(into = AppError)]
+/// enum ReadError {
+///     Io(std::io::Error),
+///     Utf8(std::str::Utf8Error),
+/// }
+///
+/// fn read() -> Result<String, ReadError> {
+///     let bytes = std::fs::read("/this/file/does/not/exist")?;
+///     Ok(std::str::from_utf8(&bytes)?.to_owned())
+/// }
+///
+/// fn run() -> Result<String, AppError> {
+///     Ok(read()?)
+/// }
+///
+/// # fn main() {
+/// let error = run().unwrap_err();
+/// assert!(matches!(error, AppError::Io(_)));
+/// assert!(matches!(ReadError::try_from(error), Ok(ReadError::Io(_))));
+/// assert!(ReadError::try_from(AppError::Config("missing".into())).is_err());
+/// # }
+/// ```
+/// ### Newtypes
+/// A struct that wraps a single value gets a From impl too, from the type of its field.
+/// With options on the struct, it also gets:
+/// * `into_inner`: a From impl taking the value back out of the struct,
+///   unless the value is a type parameter, which can't be converted into
+/// * `deref`: a [Deref](std::ops::Deref) impl to the value
+/// * `as_ref`: an [AsRef] impl to the value
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug, PartialEq)]
+/// #[ace_it(into_inner, deref)]
+/// struct UserId(u64);
+///
+/// #[ace_it(as_ref)]
+/// struct Name {
+///     inner: String,
+/// }
+///
+/// let id = UserId::from(7);
+/// assert_eq!(*id, 7);
+/// assert_eq!(u64::from(id), 7);
+///
+/// let name: Name = String::from("ace").into();
+/// let name: &String = name.as_ref();
+/// assert_eq!(name, "ace");
+/// ```
+/// ### Delegating traits
+/// `#[ace_it(delegate(Trait, ...))]` implements traits for the enum by forwarding every method to the value of the variant,
+/// so every variant has to wrap a single value.
+/// The well-known std traits `Read`, `BufRead`, `Write`, `Seek`, `Iterator`, `DoubleEndedIterator`, `ExactSizeIterator`,
+/// `Future`, `Debug`, `Display` and `Error` are named by themselves or by a path ending in their module, like `io::Read`.
+/// Other traits have to be marked with [macro@ace_trait], the enum fails with "cannot find macro" otherwise.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// use std::io::Read;
+///
+/// #[ace_it(delegate(Read))]
+/// enum Input {
+///     Bytes(&'static [u8]),
+///     Empty(std::io::Empty),
+/// }
+///
+/// let mut input = Input::from(&b"ace"[..]);
+/// let mut read = String::new();
+/// input.read_to_string(&mut read).unwrap();
+/// assert_eq!(read, "ace");
+/// ```
+/// The associated types are the ones of the type wrapped by the first variant, the other types need to have the same ones.
+/// Supertraits are delegated separately, and the variants have to agree on their associated types too.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it(delegate(Iterator, DoubleEndedIterator))]
+/// enum Numbers<A, B> {
+///     #[ace_it(skip)]
+///     Range(A),
+///     #[ace_it(skip)]
+///     Listed(B),
+/// }
+///
+/// let numbers: Numbers<std::ops::Range<u8>, std::vec::IntoIter<u8>> = Numbers::Range(1..4);
+/// assert_eq!(numbers.rev().collect::<Vec<_>>(), [3, 2, 1]);
+/// ```
+/// Methods taking `self: Pin<&mut Self>`, like [Future::poll](std::future::Future::poll), pin the value of the variant.
+/// So the enum is only Unpin if all the wrapped types are, and it can't implement Drop, which could move them out.
+/// ```compile_fail
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_it(delegate(Future))]
+/// enum Task {
+///     Ready(std::future::Ready<u8>),
+///     Pending(std::future::Pending<u8>),
+/// }
+///
+/// impl Drop for Task {
+///     fn drop(&mut self) {}
+/// }
+/// ```
+/// ### Conditional and deprecated variants
+/// The `cfg`s of a variant, and its `cfg_attr`s that set a `cfg`, are put on the code generated for it,
+/// so it's left out along with the variant. Exported enums pass them on to the enums flattening them or converted into them.
+/// The code using a `#[deprecated]` variant allows it, and its accessors are deprecated too.
+/// Trait impls can't be deprecated, so its From impls aren't.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug)]
+/// #[ace_it(accessors)]
+/// enum Error {
+///     Io(std::io::Error),
+///     #[cfg(feature = "tls")]
+///     Tls(String),
+///     #[deprecated(note = "use `Error::Io` instead")]
+///     Os(i32),
+/// }
+///
+/// assert!(Error::from(std::io::Error::other("closed")).is_io());
+/// ```
+#[proc_macro_attribute]
+pub fn ace_it(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let parsed = match syn::parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    expand_item(false, args.into(), parsed).into()
+}
+
+/// Derives the same impls as the [macro@ace_it] attribute, without emitting the enum again.
+///
+/// The enum is configured with `#[ace_it(...)]` on it, and the variants and fields like with the attribute.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[derive(Debug, AceIt)]
+/// #[ace_it(error, display)]
+/// enum Error {
+///     Io(std::io::Error),
+///     #[ace_it(display = "invalid number: {0}")]
+///     ParseInt(std::num::ParseIntError),
+///     #[ace_it(skip)]
+///     Other(String),
+/// }
+///
+/// fn parse(input: &str) -> Result<i32, Error> {
+///     Ok(input.parse()?)
+/// }
+///
+/// assert_eq!(parse("ace").unwrap_err().to_string(), "invalid number: invalid digit found in string");
+/// ```
+/// As the derive can't change the enum, `#[ace_it(boxed)]` can't be set on it.
+#[proc_macro_derive(AceIt, attributes(ace_it, from, source))]
+pub fn derive_ace_it(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let parsed: syn::Item = match syn::parse(input) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let attrs = match &parsed {
+        syn::Item::Enum(parsed) => &parsed.attrs,
+        syn::Item::Struct(parsed) => &parsed.attrs,
+        _ => &Vec::new(),
+    };
+
+    let args = match option_args(attrs) {
+        Ok(args) => args,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    expand_item(true, args, parsed).into()
+}
+
+/// Declares enums from the types they wrap, naming the variants after the last segments of their paths.
+///
+/// Each enum is declared as `vis enum Name = Type | Type | ...;`, with `as Name` after a type to name its variant.
+/// It's then expanded like with the [macro@ace_it] attribute, which is configured with `#[ace_it(...)]` on the enum.
+/// Attributes can be put on the enum and before each type, to put them on its variant.
+/// ```
+/// # use std::num::ParseIntError;
+/// #[derive(Debug)]
+/// struct MyErr;
+///
+/// ace_it::ace_enum! {
+///     #[derive(Debug)]
+///     #[ace_it(try_from)]
+///     pub enum Error = std::io::Error | ParseIntError | MyErr | #[ace_it(skip)] String as Message;
+/// }
+///
+/// fn parse(input: &str) -> Result<i32, Error> {
+///     Ok(input.parse()?)
+/// }
+///
+/// assert!(matches!(parse("ace"), Err(Error::ParseIntError(_))));
+/// assert!(matches!(Error::from(MyErr), Error::MyErr(MyErr)));
+/// let _ = Error::Message(String::from("not converted from String"));
+/// ```
+/// Types that aren't paths have to be named with `as Name`,
+/// and types named the same, like `std::io::Error` and `std::fmt::Error`, have to be renamed.
+#[proc_macro]
+pub fn ace_enum(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    match syn::parse::<ace_enum::Declarations>(input) {
+        Ok(declarations) => declarations.expand().into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Marks a trait so enums can implement it with `#[ace_it(delegate(Trait))]`, forwarding to the values of their variants.
+///
+/// It generates a macro named after the trait, which passes the signatures of its methods to the enums.
+/// So the trait has to be in scope where the enum is, and the types in its methods have to be too.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[ace_trait]
+/// trait Handler {
+///     fn handle(&mut self, request: &str) -> String;
+///     fn name(&self) -> &str {
+///         "handler"
+///     }
+/// }
+///
+/// struct Echo;
+/// impl Handler for Echo {
+///     fn handle(&mut self, request: &str) -> String {
+///         request.to_owned()
+///     }
+/// }
+///
+/// struct Shout;
+/// impl Handler for Shout {
+///     fn handle(&mut self, request: &str) -> String {
+///         request.to_uppercase()
+///     }
+///     fn name(&self) -> &str {
+///         "shout"
+///     }
+/// }
+///
+/// #[ace_it(delegate(Handler))]
+/// enum AnyHandler {
+///     Echo(Echo),
+///     Shout(Shout),
+/// }
+///
+/// let mut handler = AnyHandler::from(Shout);
+/// assert_eq!(handler.handle("ace"), "ACE");
+/// assert_eq!(handler.name(), "shout");
+/// ```
+/// The trait can't be generic. Its methods have to take `self`, by value, by reference or as `Pin<&mut Self>`,
+/// and can't take or return `Self` otherwise. Methods that don't take `self` and associated consts
+/// are left to their defaults, and can't be delegated without one.
+/// The associated types are the ones of the type wrapped by the first variant.
+///
+/// Supertraits aren't delegated along with the trait, they have to be delegated too.
+#[proc_macro_attribute]
+pub fn ace_trait(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    if let Some(arg) = TokenStream::from(args).into_iter().next() {
+        return syn::Error::new(arg.span(), "`#[ace_trait]` doesn't take options")
+            .to_compile_error()
+            .into();
+    }
+    match syn::parse(input) {
+        Ok(parsed) => delegate::process_trait(parsed).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Lets a function return values of different types from its branches, as an `impl Trait` of the given traits.
+///
+/// The values are put in the variants of an enum declared in the function,
+/// which implements the traits like with `#[ace_it(delegate(Trait, ...))]`.
+/// The tails of the branches of an `if` or a `match` ending the function are put in the enum,
+/// along with any expression marked with `ace!(...)`, like the value of an early `return`.
+/// Branches that end with `return`, `break`, `continue`, `panic!`, `unreachable!`, `todo!` or `unimplemented!` are left alone.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[auto_enum(Iterator)]
+/// fn numbers(count: u8) -> impl Iterator<Item = u8> {
+///     if count == 0 {
+///         return ace!(std::iter::empty());
+///     }
+///     match count {
+///         1 => std::iter::once(1),
+///         _ => (1..=count).rev(),
+///     }
+/// }
+///
+/// assert_eq!(numbers(0).count(), 0);
+/// assert_eq!(numbers(1).collect::<Vec<_>>(), [1]);
+/// assert_eq!(numbers(3).collect::<Vec<_>>(), [3, 2, 1]);
+/// ```
+/// With `Error`, the enum also gets `Debug` and `Display`, which it requires.
+/// ```
+/// # #[macro_use] extern crate ace_it;
+/// #[auto_enum(std::error::Error)]
+/// fn parse_error(input: &str) -> impl std::error::Error {
+///     match input.parse::<i32>() {
+///         Ok(_) => std::fmt::Error,
+///         Err(e) => e,
+///     }
+/// }
+///
+/// assert_eq!(parse_error("ace").to_string(), "invalid digit found in string");
+/// ```
+/// Markers in closures, async blocks and nested items aren't put in the enum.
+#[proc_macro_attribute]
+pub fn auto_enum(
+    args: proc_macro::TokenStream,
+    input: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let traits = match syn::parse(args) {
+        Ok(traits) => traits,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    match syn::parse(input) {
+        Ok(parsed) => auto_enum::process_auto_enum(traits, parsed).into(),
+        Err(e) => e.to_compile_error().into(),
+    }
+}
+
+/// Returns the options set with `#[ace_it(...)]` attributes, joined into a single list.
+fn option_args(attrs: &[Attribute]) -> syn::Result<TokenStream> {
+    let mut args = Vec::new();
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("ace_it")) {
+        if attr.tokens.is_empty() {
+            continue;
+        }
+        args.push(attr.parse_args::<TokenStream>()?);
+    }
+    Ok(quote!(#(#args),*))
+}
+
+/// Expands an enum, or a struct that wraps a single value.
+fn expand_item(derive: bool, args: TokenStream, parsed: syn::Item) -> TokenStream {
+    match parsed {
+        syn::Item::Enum(parsed) => {
+            let mode = if derive {
+                Mode::Derive
+            } else {
+                Mode::Attribute
+            };
+            expand(mode, args, parsed)
+        }
+        syn::Item::Struct(parsed) => newtype::expand(derive, args, parsed),
+        parsed => syn::Error::new_spanned(
+            parsed,
+            "`ace_it` can only be used on enums and structs that wrap a single value",
+        )
+        .to_compile_error(),
+    }
+}
+
+/// Called back by the macro of an exported enum with the types it's converted from,
+/// to expand an enum with `#[ace_it(flatten)]` variants.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __ace_it_flatten(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let flatten::Flattened { mode, args, item } = match syn::parse(input) {
+        Ok(flattened) => flattened,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    expand(Mode::from_ident(&mode), args, item).into()
+}
+
+/// Called back by the macro of an exported enum with the types it's converted from and the patterns of its variants,
+/// to expand an enum with `#[ace_it(into = ...)]`.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __ace_it_into(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let into::Resolved {
+        mode,
+        args,
+        item,
+        target,
+    } = match syn::parse(input) {
+        Ok(resolved) => resolved,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    let mut options: EnumOptions = match syn::parse2(args) {
+        Ok(options) => options,
+        Err(e) => return e.to_compile_error().into(),
+    };
+    options.into_target = Some(target);
+    options.mode = Mode::from_ident(&mode);
+
+    ace_it_impl(options, item).into()
+}
+
+/// Called back by the macro of a trait marked with `#[ace_trait]` with its definition,
+/// to expand an enum delegating it.
+#[doc(hidden)]
+#[proc_macro]
+pub fn __ace_it_delegate(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let delegate::Resolved { mode, args, item } = match syn::parse(input) {
+        Ok(resolved) => resolved,
+        Err(e) => return e.to_compile_error().into(),
+    };
+
+    expand(Mode::from_ident(&mode), args, item).into()
+}
+
+/// How the enum is expanded, passed along the calls resolving it to expand it the same way at the end.
+#[derive(Clone, Copy, Default, PartialEq)]
+enum Mode {
+    /// With the `#[ace_it]` attribute, emitting the enum along with the impls.
+    #[default]
+    Attribute,
+    /// With `#[derive(AceIt)]`, emitting only the impls.
+    Derive,
+    /// With the `#[ace_it]` attribute, after the enum was emitted before resolving it, emitting only the impls.
+    Emitted,
+}
+
+impl Mode {
+    /// Returns the mode passed along as the identifier.
+    fn from_ident(ident: &Ident) -> Self {
+        if ident == "derive" {
+            Self::Derive
+        } else if ident == "emitted" {
+            Self::Emitted
+        } else {
+            Self::Attribute
+        }
+    }
+
+    /// Returns the identifier the mode is passed along as.
+    fn ident(self) -> Ident {
+        match self {
+            Self::Attribute => format_ident!("attribute"),
+            Self::Derive => format_ident!("derive"),
+            Self::Emitted => format_ident!("emitted"),
+        }
+    }
+}
+
+/// Expands the enum, unless it has `#[ace_it(flatten)]` variants, delegated traits or an `into` enum to resolve first.
+///
+/// Those are resolved through the macros of other enums and traits, which may be missing.
+/// So with the attribute, the enum is emitted right away and only the impls are left for the end.
+fn expand(mode: Mode, args: TokenStream, parsed: syn::ItemEnum) -> TokenStream {
+    let mut options: EnumOptions = match syn::parse2(args.clone()) {
+        Ok(options) => options,
+        Err(e) => return e.to_compile_error(),
+    };
+    options.mode = mode;
+
+    let chained_mode = match mode {
+        Mode::Attribute => Mode::Emitted,
+        mode => mode,
+    };
+    let chain_ident = chained_mode.ident();
+    let chain = match flatten::flatten_chain(&chain_ident, &args, &parsed) {
+        Some(chain) => chain,
+        None => match delegate::delegate_chain(&options.delegate, &chain_ident, &args, &parsed) {
+            Some(chain) => Ok(chain),
+            None => match &options.into {
+                Some(path) => Ok(into::into_chain(path, &chain_ident, &args, &parsed)),
+                None => return ace_it_impl(options, parsed),
+            },
+        },
+    };
+
+    let mut tokens = if mode == Mode::Attribute {
+        let mut emitted = parsed.clone();
+        prepare_variants(&options, &mut emitted, &mut Errors::default());
+        emitted.to_token_stream()
+    } else {
+        TokenStream::new()
+    };
+    match chain {
+        Ok(chain) => chain.to_tokens(&mut tokens),
+        Err(e) => e.to_compile_error().to_tokens(&mut tokens),
+    }
+    tokens
+}
+
+/// Parses a comma separated list of options.
+///
+/// `parse_option` gets the name of each option and returns false if it doesn't know it.
+fn parse_options(
+    input: ParseStream,
+    kind: &str,
+    mut parse_option: impl FnMut(&Ident, ParseStream) -> syn::Result<bool>,
+) -> syn::Result<()> {
+    while !input.is_empty() {
+        let option: Ident = input.parse()?;
+        if !parse_option(&option, input)? {
+            return Err(syn::Error::new(
+                option.span(),
+                format!("Unknown {} option `{}`", kind, option),
+            ));
+        }
+
+        if input.is_empty() {
+            break;
+        }
+        input.parse::<Token![,]>()?;
+    }
+    Ok(())
+}
+
+/// Options that can be set on the enum with `#[ace_it(...)]`.
+#[derive(Default)]
+struct EnumOptions {
+    /// Generate TryFrom impls that take the wrapped values back out of the enum.
+    try_from: bool,
+    /// Generate an [std::error::Error] impl with `source` returning the wrapped errors.
+    error: bool,
+    /// Generate a [std::fmt::Display] impl.
+    display: bool,
+    /// Generate `is_*`, `as_*`, `as_*_mut` and `into_*` methods for the variants.
+    accessors: bool,
+    /// Box the wrapped value of every variant, converting from the unboxed one.
+    boxed: bool,
+    /// Generate `Inject` and `Project` impls for the wrapped types.
+    membership: bool,
+    /// Generate a macro that passes the types the enum is converted from to enums flattening it.
+    export: bool,
+    /// Traits to implement by forwarding to the value of every variant.
+    delegate: Vec<delegate::Delegate>,
+    /// Enum to generate a From impl into, and a TryFrom impl back out of.
+    into: Option<syn::Path>,
+    /// What the macro of the `into` enum passes back about it.
+    into_target: Option<into::Target>,
+    /// How the enum is expanded.
+    mode: Mode,
+}
+
+impl Parse for EnumOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+        parse_options(input, "enum", |option, input| {
+            match option.to_string().as_str() {
+                "try_from" => options.try_from = true,
+                "error" => options.error = true,
+                "display" => options.display = true,
+                "accessors" => options.accessors = true,
+                "boxed" => options.boxed = true,
+                "export" => options.export = true,
+                "membership" => options.membership = true,
+                "delegate" => {
+                    let delegates;
+                    syn::parenthesized!(delegates in input);
+                    options
+                        .delegate
+                        .extend(Punctuated::<_, Token![,]>::parse_terminated(&delegates)?);
+                }
+                "into" => {
+                    input.parse::<Token![=]>()?;
+                    options.into = Some(input.parse()?);
+                }
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(options)
+    }
+}
+
+/// Options that can be set on a variant with `#[ace_it(...)]`.
+#[derive(Default)]
+struct VariantOptions {
+    /// Don't generate a From impl for the variant.
+    skip: bool,
+    /// Convert to this variant when other variants wrap the same type.
+    primary: bool,
+    /// The variant wraps a Box, Arc or Rc, and is also converted from the value behind it.
+    boxed: bool,
+    /// Additional types the variant is converted from, through [Into] or `with`,
+    /// or the types a unit variant is converted from, dropping the value.
+    from: Vec<Source>,
+    /// Function the variant is converted with, instead of being converted from the wrapped value.
+    with: Option<Expr>,
+    /// Index of the field marked with `#[from]` or `#[source]`.
+    from_field: Option<usize>,
+    /// Format string used for the variant in the generated Display impl.
+    display: Option<LitStr>,
+}
+
+/// A type set with `from(...)`, with the attributes to put on the code generated for it.
+///
+/// Flattened enums pass on the `cfg`s of their variants this way.
+struct Source {
+    attrs: Vec<Attribute>,
+    ty: Type,
+}
+
+impl Parse for Source {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Ok(Self {
+            attrs: input.call(Attribute::parse_outer)?,
+            ty: input.parse()?,
+        })
+    }
+}
+
+impl ToTokens for Source {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        for attr in &self.attrs {
+            attr.to_tokens(tokens);
+        }
+        self.ty.to_tokens(tokens);
+    }
+}
+
+impl Parse for VariantOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+        options.parse_into(input)?;
+        Ok(options)
+    }
+}
+
+impl VariantOptions {
+    /// Parses a comma separated list of options, adding them to the already parsed ones.
+    fn parse_into(&mut self, input: ParseStream) -> syn::Result<()> {
+        parse_options(input, "variant", |option, input| {
+            match option.to_string().as_str() {
+                "skip" => self.skip = true,
+                "primary" => self.primary = true,
+                "boxed" => self.boxed = true,
+                "display" => {
+                    input.parse::<Token![=]>()?;
+                    self.display = Some(input.parse()?);
+                }
+                "from" if input.peek(Token![=]) => {
+                    input.parse::<Token![=]>()?;
+                    self.from.push(input.parse()?);
+                }
+                "from" => {
+                    let content;
+                    syn::parenthesized!(content in input);
+                    self.from
+                        .extend(content.parse_terminated::<_, Token![,]>(Source::parse)?);
+                }
+                "with" => {
+                    input.parse::<Token![=]>()?;
+                    self.with = Some(input.parse()?);
+                }
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })
+    }
+}
+
+/// Returns true for the attributes that mark the field a variant is converted from.
+fn is_from_field_marker(attr: &Attribute) -> bool {
+    attr.path.is_ident("from") || attr.path.is_ident("source")
+}
+
+/// Removes the `#[ace_it(...)]` attributes from the variant and the `#[from]`/`#[source]` markers from its fields,
+/// returning the options they set.
+fn take_variant_options(variant: &mut Variant) -> syn::Result<VariantOptions> {
+    let mut options = VariantOptions::default();
+    let mut error = None;
+
+    variant.attrs.retain(|attr| {
+        if !attr.path.is_ident("ace_it") {
+            return true;
+        }
+        if let Err(e) = attr.parse_args_with(|input: ParseStream| options.parse_into(input)) {
+            error.get_or_insert(e);
+        }
+        false
+    });
+
+    for (index, field) in variant.fields.iter_mut().enumerate() {
+        let Some(marker) = field.attrs.iter().find(|attr| is_from_field_marker(attr)) else {
+            continue;
+        };
+        if options.from_field.is_some() {
+            error.get_or_insert(syn::Error::new(
+                marker.span(),
+                "Only one field of a variant can be marked with `#[from]` or `#[source]`",
+            ));
+        }
+        options.from_field = Some(index);
+        field.attrs.retain(|attr| !is_from_field_marker(attr));
+    }
+
+    match error {
+        Some(e) => Err(e),
+        None => Ok(options),
+    }
+}
+
+/// How the value a From impl gets is turned into the value the variant wraps.
+#[derive(Clone, Copy)]
+enum Via<'a> {
+    /// The value is wrapped as is.
+    Direct,
+    /// The value is converted with [Into], e.g. into the [Box] the variant wraps,
+    /// or from one of the types in `#[ace_it(from(...))]`.
+    Into,
+    /// The value is passed to the function from `#[ace_it(with = ...)]`,
+    /// which returns either the wrapped value or the whole enum.
+    With(&'a Expr),
+    /// The value is dropped, the variant is a unit variant.
+    Discard,
+}
+
+/// A From impl of a variant.
+struct Conversion<'a> {
+    variant: &'a Variant,
+    /// The type the variant is converted from.
+    source: Type,
+    /// The field the variant is converted from, or [None] if it's converted from all of its unnamed fields.
+    field: Option<usize>,
+    /// How the value is turned into the value of the field.
+    via: Via<'a>,
+    /// Whether the variant is the one to convert to when other variants are converted from the same type.
+    primary: bool,
+    /// Attributes of the type from `from(...)`, put on the code generated for it along with the ones of the variant.
+    attrs: &'a [Attribute],
+}
+
+impl<'a> Conversion<'a> {
+    /// Returns the From impls the variant gets.
+    fn for_variant(variant: &'a Variant, options: &'a VariantOptions) -> syn::Result<Vec<Self>> {
+        if options.skip {
+            return Ok(Vec::new());
+        }
+
+        let (source, field) = match (&variant.fields, options.from_field) {
+            (fields, Some(index)) => (fields.iter().nth(index).unwrap().ty.clone(), Some(index)),
+            (Fields::Unnamed(fields), None) => (source_type(fields), None),
+            _ if options.boxed => {
+                return Err(syn::Error::new(
+                    variant.ident.span(),
+                    "`#[ace_it(boxed)]` needs the variant to wrap a Box, Arc or Rc",
+                ))
+            }
+            (Fields::Unit, None) => {
+                let via = options.with.as_ref().map_or(Via::Discard, Via::With);
+                return Ok(options
+                    .from
+                    .iter()
+                    .map(|source| Self {
+                        variant,
+                        source: source.ty.clone(),
+                        field: None,
+                        via,
+                        primary: options.primary,
+                        attrs: &source.attrs,
+                    })
+                    .collect());
+            }
+            _ => {
+                // Without a marked field there's no value to convert from, only a function returning the enum.
+                if let Some(with) = &options.with {
+                    return Self::with(variant, options, with, None);
+                }
+                if let Some(source) = options.from.first() {
+                    return Err(syn::Error::new_spanned(
+                        &source.ty,
+                        "`#[ace_it(from(...))]` needs a field marked with `#[from]` on a variant with named fields",
+                    ));
+                }
+                if options.primary {
+                    return Err(syn::Error::new(
+                        variant.ident.span(),
+                        "`#[ace_it(primary)]` needs a field marked with `#[from]` on a variant with named fields",
+                    ));
+                }
+                return Ok(Vec::new());
+            }
+        };
+
+        let single = field.is_some() || variant.fields.len() == 1;
+        let pointee = if options.boxed {
+            let pointee = pointee_type(&source).filter(|_| single).ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &source,
+                    "`#[ace_it(boxed)]` needs the variant to wrap a Box, Arc or Rc",
+                )
+            })?;
+            Some(pointee.clone())
+        } else {
+            None
+        };
+
+        if let Some(with) = &options.with {
+            return Self::with(variant, options, with, field);
+        }
+        if !single && !options.from.is_empty() {
+            return Err(syn::Error::new_spanned(
+                &options.from[0].ty,
+                "`#[ace_it(from(...))]` needs the variant to wrap a single value",
+            ));
+        }
+
+        let mut conversions = vec![Self {
+            variant,
+            source,
+            field,
+            via: Via::Direct,
+            primary: options.primary,
+            attrs: &[],
+        }];
+        let sources = pointee.into_iter().map(|pointee| (pointee, &[][..])).chain(
+            options
+                .from
+                .iter()
+                .map(|source| (source.ty.clone(), &source.attrs[..])),
+        );
+        for (source, attrs) in sources {
+            conversions.push(Self {
+                variant,
+                source,
+                field,
+                via: Via::Into,
+                primary: options.primary,
+                attrs,
+            });
+        }
+
+        Ok(conversions)
+    }
+
+    /// Returns the conversions of a variant converted with the function from `#[ace_it(with = ...)]`.
+    fn with(
+        variant: &'a Variant,
+        options: &'a VariantOptions,
+        with: &'a Expr,
+        field: Option<usize>,
+    ) -> syn::Result<Vec<Self>> {
+        if options.from.is_empty() {
+            return Err(syn::Error::new_spanned(
+                with,
+                "`#[ace_it(with = ...)]` needs the type it converts from, set it with `from = Type`",
+            ));
+        }
+        Ok(options
+            .from
+            .iter()
+            .map(|source| Self {
+                variant,
+                source: source.ty.clone(),
+                field,
+                via: Via::With(with),
+                primary: options.primary,
+                attrs: &source.attrs,
+            })
+            .collect())
+    }
+
+    /// Returns the attributes the code generated for the conversion has to carry, see [forwarded_attrs].
+    fn attrs(&self) -> TokenStream {
+        let mut attrs = forwarded_attrs(self.variant);
+        for attr in self.attrs {
+            attr.to_tokens(&mut attrs);
+        }
+        attrs
+    }
+
+    /// Returns the type of the value the variant wraps,
+    /// or [None] for a variant with named fields and none of them marked, which only a function can build.
+    fn wrapped_type(&self) -> Option<Type> {
+        match (self.field, &self.variant.fields) {
+            (Some(index), fields) => Some(fields.iter().nth(index).unwrap().ty.clone()),
+            (None, Fields::Unnamed(fields)) => Some(source_type(fields)),
+            (None, Fields::Unit) => Some(syn::parse_quote!(())),
+            (None, Fields::Named(_)) => None,
+        }
+    }
+
+    /// Returns the pattern binding the wrapped value and the expression building the variant out of it.
+    fn construct(&self, enum_path: &TokenStream) -> (TokenStream, TokenStream) {
+        let variant_name = &self.variant.ident;
+
+        let Some(index) = self.field else {
+            let len = self.variant.fields.len();
+            if let Fields::Unit = self.variant.fields {
+                return (quote!(()), quote!(#enum_path::#variant_name));
+            }
+            if len == 1 {
+                return (quote!(value), quote!(#enum_path::#variant_name(value)));
+            }
+            let values = (0..len).map(|i| format_ident!("value{}", i));
+            let values = quote!(#(#values),*);
+            return (
+                quote!((#values)),
+                quote!(#enum_path::#variant_name(#values)),
+            );
+        };
+
+        let fields = self.variant.fields.iter().enumerate().map(|(i, field)| {
+            let value = if i == index {
+                quote!(value)
+            } else {
+                let ty = &field.ty;
+                quote_spanned!(ty.span()=> <#ty as ::core::default::Default>::default())
+            };
+            match &field.ident {
+                Some(ident) => quote!(#ident: #value),
+                None => value,
+            }
+        });
+        let body = match &self.variant.fields {
+            Fields::Named(_) => quote!(#enum_path::#variant_name { #(#fields),* }),
+            _ => quote!(#enum_path::#variant_name(#(#fields),*)),
+        };
+        (quote!(value), body)
+    }
+}
+
+/// Returns true if the type is a `Box`, `Arc` or `Rc`, whatever it points to.
+fn is_pointer(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    path.qself.is_none()
+        && path.path.segments.last().is_some_and(|segment| {
+            ["Box", "Arc", "Rc"]
+                .iter()
+                .any(|name| segment.ident == name)
+                && matches!(segment.arguments, PathArguments::AngleBracketed(_))
+        })
+}
+
+/// Returns `T` if the type is `Box<T>`, `Arc<T>` or `Rc<T>`, where `T` is sized.
+fn pointee_type(ty: &Type) -> Option<&Type> {
+    if !is_pointer(ty) {
+        return None;
+    }
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let PathArguments::AngleBracketed(arguments) = &path.path.segments.last()?.arguments else {
+        return None;
+    };
+    let mut arguments = arguments.args.iter();
+    let (Some(GenericArgument::Type(pointee)), None) = (arguments.next(), arguments.next()) else {
+        return None;
+    };
+    match pointee {
+        Type::TraitObject(_) | Type::Slice(_) => None,
+        Type::Path(path) if path.path.is_ident("str") => None,
+        _ => Some(pointee),
+    }
+}
+
+/// Makes the variant wrap its value in a [Box], unless it's already behind a pointer, and marks it `boxed`.
+///
+/// Pointers to unsized types, like `Box<dyn Error>`, are left alone, as they can't be converted from what they point to.
+fn box_wrapped_field(variant: &mut Variant, options: &mut VariantOptions) {
+    if options.skip {
+        return;
+    }
+    let index = match (&variant.fields, options.from_field) {
+        (_, Some(index)) => index,
+        (Fields::Unnamed(fields), None) if fields.unnamed.len() == 1 => 0,
+        _ => return,
+    };
+
+    let field = variant.fields.iter_mut().nth(index).unwrap();
+    if !is_pointer(&field.ty) {
+        let ty = &field.ty;
+        field.ty = syn::parse_quote!(::std::boxed::Box<#ty>);
+    } else if pointee_type(&field.ty).is_none() {
+        return;
+    }
+    options.boxed = true;
+}
+
+impl Conversion<'_> {
+    /// Returns the pattern matching the variant and the expression of the source type it binds.
+    fn destructure(&self, enum_path: &TokenStream) -> (TokenStream, TokenStream) {
+        let variant_name = &self.variant.ident;
+
+        let Some(index) = self.field else {
+            let len = self.variant.fields.len();
+            if len == 1 {
+                return (quote!(#enum_path::#variant_name(value)), quote!(value));
+            }
+            let values = (0..len).map(|i| format_ident!("value{}", i));
+            let values = quote!(#(#values),*);
+            return (
+                quote!(#enum_path::#variant_name(#values)),
+                quote!((#values)),
+            );
+        };
+
+        let pattern = match &self
+            .variant
+            .fields
+            .iter()
+            .nth(index)
+            .and_then(|field| field.ident.as_ref())
+        {
+            Some(ident) => quote!(#enum_path::#variant_name { #ident: value, .. }),
+            None => {
+                let skipped = (0..index).map(|_| quote!(_));
+                quote!(#enum_path::#variant_name(#(#skipped,)* value, ..))
+            }
+        };
+        (pattern, quote!(value))
+    }
+
+    /// Returns true if the variant holds the converted value as is, so it can be borrowed from it.
+    fn holds_source(&self) -> bool {
+        self.field.is_some() || self.variant.fields.len() == 1
+    }
+}
+
+/// Returns the type a variant with unnamed fields is converted from.
+///
+/// That's the type of the field if there's only one, otherwise it's a tuple of all of them.
+fn source_type(fields: &FieldsUnnamed) -> Type {
+    if fields.unnamed.len() == 1 {
+        return fields.unnamed[0].ty.clone();
+    }
+
+    let elems = fields.unnamed.iter().map(|field| &field.ty);
+    syn::parse_quote!((#(#elems),*))
+}
+
+/// Generates From impls for the given enum.
+fn process_variants(
+    conversions: &[Conversion],
+    enum_name: &Ident,
+    generics: &Generics,
+) -> Vec<TokenStream> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut from_impls = Vec::new();
+
+    for conversion in conversions {
+        let source = &conversion.source;
+        let (pattern, body) = conversion.construct(&quote!(Self));
+        let imp = match conversion.via {
+            Via::Direct => quote! {
+                impl #impl_generics From<#source> for #enum_name #ty_generics #where_clause {
+                    fn from(#pattern: #source) -> Self {
+                        #body
+                    }
+                }
+            },
+            Via::Into => quote! {
+                impl #impl_generics From<#source> for #enum_name #ty_generics #where_clause {
+                    fn from(value: #source) -> Self {
+                        let #pattern = ::core::convert::Into::into(value);
+                        #body
+                    }
+                }
+            },
+            Via::Discard => quote! {
+                impl #impl_generics From<#source> for #enum_name #ty_generics #where_clause {
+                    fn from(_: #source) -> Self {
+                        #body
+                    }
+                }
+            },
+            Via::With(function) => match conversion.wrapped_type() {
+                None => quote! {
+                    impl #impl_generics From<#source> for #enum_name #ty_generics #where_clause {
+                        fn from(value: #source) -> Self {
+                            (#function)(value)
+                        }
+                    }
+                },
+                // The function can return either the wrapped value or the enum,
+                // so the result goes through a trait implemented for both of them.
+                Some(wrapped) => {
+                    let (pattern, body) = conversion.construct(&quote!(#enum_name));
+                    quote! {
+                        const _: () = {
+                            trait __AceItWith<E> {
+                                fn __ace_it_with(self) -> E;
+                            }
+
+                            impl #impl_generics __AceItWith<#enum_name #ty_generics> for #enum_name #ty_generics #where_clause {
+                                fn __ace_it_with(self) -> #enum_name #ty_generics {
+                                    self
+                                }
+                            }
+
+                            impl #impl_generics __AceItWith<#enum_name #ty_generics> for #wrapped #where_clause {
+                                fn __ace_it_with(self) -> #enum_name #ty_generics {
+                                    let #pattern = self;
+                                    #body
+                                }
+                            }
+
+                            impl #impl_generics From<#source> for #enum_name #ty_generics #where_clause {
+                                fn from(value: #source) -> Self {
+                                    <_ as __AceItWith<Self>>::__ace_it_with((#function)(value))
+                                }
+                            }
+                        };
+                    }
+                }
+            },
+        };
+
+        let attrs = conversion.attrs();
+        from_impls.push(quote!(#attrs #imp));
+    }
+
+    from_impls
+}
+
+/// Generates TryFrom impls that take the converted values back out of the enum, by value and by reference.
+fn process_try_from(
+    conversions: &[Conversion],
+    enum_name: &Ident,
+    generics: &Generics,
+) -> Vec<TokenStream> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let enum_path = quote!(#enum_name);
+    let mut ref_generics = generics.clone();
+    ref_generics.params.insert(0, syn::parse_quote!('__ace_it));
+    let (ref_impl_generics, _, _) = ref_generics.split_for_impl();
+    let mut try_from_impls = Vec::new();
+
+    for conversion in conversions
+        .iter()
+        .filter(|conversion| matches!(conversion.via, Via::Direct))
+    {
+        let source = &conversion.source;
+        // `impl<T> TryFrom<Enum<T>> for Box<T>` isn't allowed, as `T` isn't a local type.
+        if uncovers_type_param(source, generics) {
+            continue;
+        }
+        let (pattern, value) = conversion.destructure(&enum_path);
+        let attrs = conversion.attrs();
+        try_from_impls.push(quote! {
+            #attrs
+            impl #impl_generics ::core::convert::TryFrom<#enum_name #ty_generics> for #source #where_clause {
+                type Error = #enum_name #ty_generics;
+
+                fn try_from(value: #enum_name #ty_generics) -> ::core::result::Result<Self, Self::Error> {
+                    match value {
+                        #pattern => ::core::result::Result::Ok(#value),
+                        #[allow(unreachable_patterns)]
+                        other => ::core::result::Result::Err(other),
+                    }
+                }
+            }
+        });
+
+        if conversion.holds_source() {
+            try_from_impls.push(quote! {
+                #attrs
+                impl #ref_impl_generics ::core::convert::TryFrom<&'__ace_it #enum_name #ty_generics> for &'__ace_it #source #where_clause {
+                    type Error = &'__ace_it #enum_name #ty_generics;
+
+                    fn try_from(value: &'__ace_it #enum_name #ty_generics) -> ::core::result::Result<Self, Self::Error> {
+                        match value {
+                            #pattern => ::core::result::Result::Ok(#value),
+                            #[allow(unreachable_patterns)]
+                            other => ::core::result::Result::Err(other),
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    try_from_impls
+}
+
+/// Generates the [Inject](../ace_it/trait.Inject.html) impls for every converted type,
+/// and the [Project](../ace_it/trait.Project.html) impls for every type a variant holds as is.
+fn process_membership(
+    conversions: &[Conversion],
+    enum_name: &Ident,
+    generics: &Generics,
+) -> Vec<TokenStream> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let mut impls = Vec::new();
+
+    for conversion in conversions {
+        let source = &conversion.source;
+        let attrs = conversion.attrs();
+        impls.push(quote! {
+            #attrs
+            impl #impl_generics ::ace_it::Inject<#source> for #enum_name #ty_generics #where_clause {
+                fn inject(value: #source) -> Self {
+                    <Self as ::core::convert::From<#source>>::from(value)
+                }
+            }
+        });
+
+        if !matches!(conversion.via, Via::Direct) || !conversion.holds_source() {
+            continue;
+        }
+        let (pattern, value) = conversion.destructure(&quote!(Self));
+        impls.push(quote! {
+            #attrs
+            impl #impl_generics ::ace_it::Project<#source> for #enum_name #ty_generics #where_clause {
+                fn project(self) -> ::core::result::Result<#source, Self> {
+                    match self {
+                        #pattern => ::core::result::Result::Ok(#value),
+                        #[allow(unreachable_patterns)]
+                        other => ::core::result::Result::Err(other),
+                    }
+                }
+
+                fn project_ref(&self) -> ::core::option::Option<&#source> {
+                    match self {
+                        #pattern => ::core::option::Option::Some(#value),
+                        #[allow(unreachable_patterns)]
+                        _ => ::core::option::Option::None,
+                    }
+                }
+            }
+        });
+    }
+
+    impls
+}
+
+/// Returns the attributes the code generated for the variant has to carry.
+///
+/// That's its `cfg`s, and `cfg_attr`s setting a `cfg`, so the code is left out along with the variant,
+/// and `#[allow(deprecated)]` if the variant is deprecated, so the code using it doesn't warn.
+fn forwarded_attrs(variant: &Variant) -> TokenStream {
+    let mut attrs = TokenStream::new();
+    for attr in &variant.attrs {
+        if attr.path.is_ident("cfg") {
+            attr.to_tokens(&mut attrs);
+        } else if attr.path.is_ident("cfg_attr") {
+            let Ok(Meta::List(list)) = attr.parse_meta() else {
+                continue;
+            };
+            let mut nested = list.nested.iter();
+            let Some(predicate) = nested.next() else {
+                continue;
+            };
+            let cfgs: Vec<_> = nested
+                .filter(
+                    |meta| matches!(meta, NestedMeta::Meta(meta) if meta.path().is_ident("cfg")),
+                )
+                .collect();
+            if !cfgs.is_empty() {
+                attrs.extend(quote!(#[cfg_attr(#predicate, #(#cfgs),*)]));
+            }
+        }
+    }
+    if deprecated_attrs(variant).next().is_some() {
+        attrs.extend(quote!(#[allow(deprecated)]));
+    }
+    attrs
+}
+
+/// Returns the `#[deprecated]` attributes of the variant.
+fn deprecated_attrs(variant: &Variant) -> impl Iterator<Item = &Attribute> {
+    variant
+        .attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("deprecated"))
+}
+
+/// Returns the field that holds the wrapped value of the variant.
+///
+/// That's the field marked with `#[from]`/`#[source]`, or the only unnamed field.
+fn wrapped_field(variant: &Variant, options: &VariantOptions) -> Option<Member> {
+    match (&variant.fields, options.from_field) {
+        (Fields::Named(fields), Some(index)) => {
+            fields.named[index].ident.clone().map(Member::Named)
+        }
+        (_, Some(index)) => Some(Member::Unnamed(index.into())),
+        (Fields::Unnamed(fields), None) if fields.unnamed.len() == 1 => {
+            Some(Member::Unnamed(0.into()))
+        }
+        _ => None,
+    }
+}
+
+/// Returns the type of the field of the variant.
+fn member_type<'a>(variant: &'a Variant, member: &Member) -> Option<&'a Type> {
+    match member {
+        Member::Named(ident) => variant
+            .fields
+            .iter()
+            .find(|field| field.ident.as_ref() == Some(ident))
+            .map(|field| &field.ty),
+        Member::Unnamed(index) => variant
+            .fields
+            .iter()
+            .nth(index.index as usize)
+            .map(|field| &field.ty),
+    }
+}
+
+/// Returns true if the type is a boxed error trait object, like `Box<dyn Error + Send + Sync>`.
+///
+/// `Box<dyn Error>` doesn't implement [std::error::Error] itself, so its source is returned by dereferencing it.
+fn is_boxed_dyn_error(ty: &Type) -> bool {
+    let Type::Path(path) = ty else {
+        return false;
+    };
+    let Some(segment) = path.path.segments.last() else {
+        return false;
+    };
+    let PathArguments::AngleBracketed(arguments) = &segment.arguments else {
+        return false;
+    };
+    let Some(GenericArgument::Type(Type::TraitObject(object))) = arguments.args.first() else {
+        return false;
+    };
+    if path.qself.is_some() || segment.ident != "Box" {
+        return false;
+    }
+    object.bounds.iter().any(|bound| {
+        matches!(bound, syn::TypeParamBound::Trait(bound) if bound.path.segments.last().is_some_and(|last| last.ident == "Error"))
+    }) && object.bounds.iter().all(|bound| {
+        !matches!(bound, syn::TypeParamBound::Lifetime(lifetime) if lifetime.ident != "static")
+    })
+}
+
+/// Generates an [std::error::Error] impl for the enum.
+///
+/// `source` returns the wrapped value of each variant if it implements [std::error::Error], and [None] otherwise.
+/// Whether it does is decided with autoref specialization, as there's no way to know it from the tokens alone.
+fn process_error<'a>(
+    variants: impl Iterator<Item = (&'a Variant, &'a VariantOptions)>,
+    enum_name: &Ident,
+    generics: &Generics,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let arms: Vec<_> = variants
+        .map(|(variant, options)| {
+            let variant_name = &variant.ident;
+            let attrs = forwarded_attrs(variant);
+            match wrapped_field(variant, options) {
+                Some(member)
+                    if member_type(variant, &member).is_some_and(is_boxed_dyn_error) =>
+                {
+                    quote! {
+                        #attrs
+                        Self::#variant_name { #member: source, .. } => ::core::option::Option::Some(&**source),
+                    }
+                }
+                // Picking the impl for errors ignores lifetimes, it would fail to borrow for `'static` afterwards.
+                Some(member)
+                    if member_type(variant, &member)
+                        .is_some_and(|ty| mentions_lifetime(ty.to_token_stream())) =>
+                {
+                    quote! {
+                        #attrs
+                        Self::#variant_name { .. } => ::core::option::Option::None,
+                    }
+                }
+                Some(member) => quote! {
+                    #attrs
+                    Self::#variant_name { #member: source, .. } => (&__AceItSource(source)).__ace_it_source(),
+                },
+                None => quote! {
+                    #attrs
+                    Self::#variant_name { .. } => ::core::option::Option::None,
+                },
+            }
+        })
+        .collect();
+    let scrutinee = if arms.is_empty() {
+        quote!(*self)
+    } else {
+        quote!(self)
+    };
+
+    quote! {
+        impl #impl_generics ::std::error::Error for #enum_name #ty_generics #where_clause {
+            fn source(&self) -> ::core::option::Option<&(dyn ::std::error::Error + 'static)> {
+                struct __AceItSource<'a, T: ?::core::marker::Sized>(&'a T);
+
+                trait __AceItIsError<'a> {
+                    fn __ace_it_source(&self) -> ::core::option::Option<&'a (dyn ::std::error::Error + 'static)>;
+                }
+                impl<'a, T: ::std::error::Error + 'static> __AceItIsError<'a> for __AceItSource<'a, T> {
+                    fn __ace_it_source(&self) -> ::core::option::Option<&'a (dyn ::std::error::Error + 'static)> {
+                        ::core::option::Option::Some(self.0)
+                    }
+                }
+
+                trait __AceItNotError<'a> {
+                    fn __ace_it_source(&self) -> ::core::option::Option<&'a (dyn ::std::error::Error + 'static)>;
+                }
+                impl<'a, T: ?::core::marker::Sized> __AceItNotError<'a> for &__AceItSource<'a, T> {
+                    fn __ace_it_source(&self) -> ::core::option::Option<&'a (dyn ::std::error::Error + 'static)> {
+                        ::core::option::Option::None
+                    }
+                }
+
+                match #scrutinee {
+                    #(#arms)*
+                }
+            }
+        }
+    }
+}
+
+/// Returns true if the type is a type parameter of the enum,
+/// or one in fundamental types like `&T` or `Box<T>`, that are treated as the parameter by the orphan rules.
+fn uncovers_type_param(ty: &Type, generics: &Generics) -> bool {
+    match ty {
+        Type::Group(group) => uncovers_type_param(&group.elem, generics),
+        Type::Paren(paren) => uncovers_type_param(&paren.elem, generics),
+        Type::Reference(reference) => uncovers_type_param(&reference.elem, generics),
+        Type::Path(path) if path.qself.is_none() => {
+            let Some(last) = path.path.segments.last() else {
+                return false;
+            };
+            match &last.arguments {
+                PathArguments::AngleBracketed(args) if last.ident == "Box" || last.ident == "Pin" => {
+                    args.args.iter().any(|arg| {
+                        matches!(arg, GenericArgument::Type(ty) if uncovers_type_param(ty, generics))
+                    })
+                }
+                _ => as_type_param(ty, generics).is_some(),
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Returns the type parameter of the enum that the type consists of, if any.
+fn as_type_param<'a>(ty: &Type, generics: &'a Generics) -> Option<&'a Ident> {
+    match ty {
+        Type::Group(group) => as_type_param(&group.elem, generics),
+        Type::Paren(paren) => as_type_param(&paren.elem, generics),
+        Type::Path(path) if path.qself.is_none() => {
+            let ident = path.path.get_ident()?;
+            generics
+                .type_params()
+                .map(|param| &param.ident)
+                .find(|param| *param == ident)
+        }
+        _ => None,
+    }
+}
+
+/// Returns true if the tokens name a lifetime other than `'static` anywhere.
+fn mentions_lifetime(tokens: TokenStream) -> bool {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            proc_macro2::TokenTree::Punct(punct)
+                if punct.as_char() == '\''
+                    && !matches!(tokens.peek(), Some(proc_macro2::TokenTree::Ident(ident)) if ident == "static") =>
+            {
+                return true;
+            }
+            proc_macro2::TokenTree::Group(group) if mentions_lifetime(group.stream()) => {
+                return true;
+            }
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Returns true if the tokens name the identifier anywhere.
+fn mentions_ident(tokens: TokenStream, ident: &Ident) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(token) => token == *ident,
+        proc_macro2::TokenTree::Group(group) => mentions_ident(group.stream(), ident),
+        _ => false,
+    })
+}
+
+/// Checks that the conversions from a bare type parameter don't overlap with other conversions,
+/// removing the ones that do.
+///
+/// `impl<T> From<T> for Enum<T>` overlaps with the From impl of any other type that doesn't contain `T`,
+/// as `T` could be that type. Rustc reports this inside the macro output, so it's diagnosed here instead.
+fn check_type_param_variants(
+    conversions: &mut Vec<Conversion>,
+    generics: &Generics,
+    errors: &mut Errors,
+) {
+    let overlapping: Vec<_> = conversions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, conversion)| {
+            let param = as_type_param(&conversion.source, generics)?;
+            let other = conversions.iter().enumerate().find(|(other, other_conversion)| {
+                *other != index
+                    && !mentions_ident(other_conversion.source.to_token_stream(), param)
+            })?;
+            Some((
+                index,
+                syn::Error::new_spanned(
+                    &conversion.source,
+                    format!(
+                        "Variant wraps the type parameter `{}`, its From impl would conflict with the one from `{}` of variant `{}` when `{}` is `{}`. Mark one of them with `#[ace_it(skip)]`",
+                        param,
+                        normalize::type_name(&other.1.source),
+                        other.1.variant.ident,
+                        param,
+                        normalize::type_name(&other.1.source),
+                    ),
+                ),
+            ))
+        })
+        .collect();
+
+    let mut index = 0;
+    conversions.retain(|_| {
+        index += 1;
+        !overlapping
+            .iter()
+            .any(|(overlapping, _)| *overlapping == index - 1)
+    });
+    for (_, error) in overlapping {
+        errors.push(error);
+    }
+}
+
+/// Collects errors, so that all of them are reported at once.
+#[derive(Default)]
+struct Errors(Option<syn::Error>);
+
+impl Errors {
+    fn push(&mut self, error: syn::Error) {
+        match &mut self.0 {
+            Some(errors) => errors.combine(error),
+            None => self.0 = Some(error),
+        }
+    }
+}
+
+impl ToTokens for Errors {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if let Some(errors) = &self.0 {
+            errors.to_compile_error().to_tokens(tokens);
+        }
+    }
+}
+
+/// Generates a warning at the given span.
+///
+/// Proc macros can't emit warnings on stable, so this uses a deprecated item to get rustc to emit one.
+/// The given `allow` attributes are put on it, so `#[allow(deprecated)]` silences it.
+fn warning<'a>(
+    span: Span,
+    message: &str,
+    allows: impl Iterator<Item = &'a Attribute>,
+) -> TokenStream {
+    let warning = quote_spanned!(span=> __AceItWarning);
+    quote! {
+        #(#allows)*
+        const _: () = {
+            #[deprecated(note = #message)]
+            struct __AceItWarning;
+            let _ = #warning;
+        };
+    }
+}
+
+/// Returns the groups of conversions that are from the same type, as indices in the order they come in.
+fn find_duplicate_variant_types(conversions: &[Conversion], self_ty: &Type) -> Vec<Vec<usize>> {
+    let mut types_map: HashMap<String, usize> = HashMap::new();
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for (index, conversion) in conversions.iter().enumerate() {
+        let types = normalize::type_key(&normalize::normalize_type(&conversion.source, self_ty));
+
+        match types_map.entry(types) {
+            Entry::Occupied(group) => groups[*group.get()].push(index),
+            Entry::Vacant(entry) => {
+                entry.insert(groups.len());
+                groups.push(vec![index]);
+            }
+        }
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// Keeps a single conversion out of the ones from the same type.
+///
+/// That's the one marked with `#[ace_it(primary)]`, if there's exactly one.
+/// Otherwise every other conversion is reported, and the first one is kept.
+fn check_duplicate_variant_types(
+    conversions: &mut Vec<Conversion>,
+    self_ty: &Type,
+    errors: &mut Errors,
+) {
+    let mut removed = Vec::new();
+    for group in find_duplicate_variant_types(conversions, self_ty) {
+        let primaries: Vec<usize> = group
+            .iter()
+            .copied()
+            .filter(|&index| conversions[index].primary)
+            .collect();
+
+        let kept = match primaries.as_slice() {
+            [primary] => *primary,
+            [] => {
+                let first = &conversions[group[0]];
+                for &duplicate in &group[1..] {
+                    let duplicate = &conversions[duplicate];
+                    errors.push(syn::Error::new_spanned(
+                        &duplicate.source,
+                        format!(
+                            "Duplicate variant type, can't auto-generate From impls. `{}` is already wrapped by variant `{}`, mark one of them with `#[ace_it(primary)]`",
+                            normalize::type_name(&duplicate.source),
+                            first.variant.ident,
+                        ),
+                    ));
+                    errors.push(syn::Error::new(
+                        first.variant.ident.span(),
+                        format!(
+                            "note: variant `{}` wraps `{}` first",
+                            first.variant.ident,
+                            normalize::type_name(&first.source),
+                        ),
+                    ));
+                }
+                group[0]
+            }
+            [first, rest @ ..] => {
+                let first = &conversions[*first];
+                for &extra in rest {
+                    let extra = &conversions[extra];
+                    errors.push(syn::Error::new(
+                        extra.variant.ident.span(),
+                        format!(
+                            "Only one of the variants wrapping `{}` can be `#[ace_it(primary)]`",
+                            normalize::type_name(&extra.source),
+                        ),
+                    ));
+                    errors.push(syn::Error::new(
+                        first.variant.ident.span(),
+                        format!("note: variant `{}` is marked first", first.variant.ident),
+                    ));
+                }
+                primaries[0]
+            }
+        };
+        removed.extend(group.into_iter().filter(|&index| index != kept));
+    }
+
+    *conversions = std::mem::take(conversions)
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| !removed.contains(index))
+        .map(|(_, conversion)| conversion)
+        .collect();
+}
+
+/// Generates warnings for the variant types that aren't the same, but might be after name resolution.
+///
+/// The `allow` attributes of the enum and of the variant are put on the warnings.
+fn warn_ambiguous_variant_types(
+    conversions: &[Conversion],
+    self_ty: &Type,
+    enum_attrs: &[Attribute],
+) -> Vec<TokenStream> {
+    let types: Vec<_> = conversions
+        .iter()
+        .map(|conversion| normalize::normalize_type(&conversion.source, self_ty))
+        .collect();
+
+    let mut warnings = Vec::new();
+    for (i, ty) in types.iter().enumerate() {
+        let Some(earlier) = types[..i]
+            .iter()
+            .position(|earlier| normalize::may_be_same(earlier, ty))
+        else {
+            continue;
+        };
+        let message = format!(
+            "`{}` might be the same type as `{}` of variant `{}`, in which case their From impls conflict",
+            normalize::type_name(&conversions[i].source),
+            normalize::type_name(&conversions[earlier].source),
+            conversions[earlier].variant.ident,
+        );
+        let allows = enum_attrs
+            .iter()
+            .chain(&conversions[i].variant.attrs)
+            .filter(|attr| attr.path.is_ident("allow"));
+        warnings.push(warning(conversions[i].source.span(), &message, allows));
+    }
+    warnings
+}
+
+/// Takes the options out of the attributes of the variants, boxing their values with `#[ace_it(boxed)]` on the enum.
+///
+/// This turns the enum into the one that's emitted.
+fn prepare_variants(
+    enum_options: &EnumOptions,
+    parsed: &mut syn::ItemEnum,
+    errors: &mut Errors,
+) -> Vec<VariantOptions> {
+    parsed
+        .variants
+        .iter_mut()
+        .map(|variant| {
+            let mut options = take_variant_options(variant).unwrap_or_else(|e| {
+                errors.push(e);
+                VariantOptions::default()
+            });
+            if enum_options.boxed {
+                box_wrapped_field(variant, &mut options);
+            }
+            options
+        })
+        .collect()
+}
+
+/// Generates everything for the enum.
+///
+/// Errors are reported along with the enum and the impls that could be generated,
+/// so they don't cascade into errors about the enum missing.
+fn ace_it_impl(mut enum_options: EnumOptions, mut parsed: syn::ItemEnum) -> TokenStream {
+    let mut errors = Errors::default();
+
+    if enum_options.mode == Mode::Derive && enum_options.boxed {
+        errors.push(syn::Error::new(
+            Span::call_site(),
+            "`#[ace_it(boxed)]` can't be set on the enum with `#[derive(AceIt)]`, as it changes the enum. Use the `#[ace_it(boxed)]` attribute instead",
+        ));
+        enum_options.boxed = false;
+    }
+
+    let options = prepare_variants(&enum_options, &mut parsed, &mut errors);
+    let mut conversions = Vec::new();
+    for (variant, options) in parsed.variants.iter().zip(&options) {
+        match Conversion::for_variant(variant, options) {
+            Ok(variant_conversions) => conversions.extend(variant_conversions),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    check_type_param_variants(&mut conversions, &parsed.generics, &mut errors);
+
+    let ident = &parsed.ident;
+    let (_, ty_generics, _) = parsed.generics.split_for_impl();
+    let self_ty: Type = syn::parse_quote!(#ident #ty_generics);
+
+    check_duplicate_variant_types(&mut conversions, &self_ty, &mut errors);
+
+    let mut enum_def = if enum_options.mode == Mode::Attribute {
+        parsed.to_token_stream()
+    } else {
+        TokenStream::new()
+    };
+
+    let for_impls = process_variants(&conversions, &parsed.ident, &parsed.generics);
+
+    for impls in for_impls {
+        impls.to_tokens(&mut enum_def);
+    }
+
+    for warning in warn_ambiguous_variant_types(&conversions, &self_ty, &parsed.attrs) {
+        warning.to_tokens(&mut enum_def);
+    }
+
+    if enum_options.membership {
+        for impls in process_membership(&conversions, &parsed.ident, &parsed.generics) {
+            impls.to_tokens(&mut enum_def);
+        }
+    }
+
+    for delegate in &enum_options.delegate {
+        match delegate::process_delegate(
+            delegate,
+            parsed.variants.iter().zip(&options),
+            &parsed.ident,
+            &parsed.generics,
+        ) {
+            Ok(imp) => imp.to_tokens(&mut enum_def),
+            Err(e) => errors.push(e),
+        }
+    }
+    if let Some(imp) = delegate::process_pinned(
+        &enum_options.delegate,
+        parsed.variants.iter().zip(&options),
+        &parsed.ident,
+        &parsed.generics,
+    ) {
+        imp.to_tokens(&mut enum_def);
+    }
+
+    if enum_options.try_from {
+        for impls in process_try_from(&conversions, &parsed.ident, &parsed.generics) {
+            impls.to_tokens(&mut enum_def);
+        }
+    }
+
+    if enum_options.display {
+        match display::process_display(
+            parsed.variants.iter().zip(&options),
+            &parsed.ident,
+            &parsed.generics,
+        ) {
+            Ok(imp) => imp.to_tokens(&mut enum_def),
+            Err(e) => errors.push(e),
+        }
+    } else if let Some(format) = options.iter().find_map(|options| options.display.as_ref()) {
+        errors.push(syn::Error::new(
+            format.span(),
+            "Display format strings require `#[ace_it(display)]` on the enum",
+        ));
+    }
+
+    if enum_options.accessors {
+        accessors::process_accessors(
+            parsed.variants.iter(),
+            &parsed.ident,
+            &parsed.vis,
+            &parsed.generics,
+        )
+        .to_tokens(&mut enum_def);
+    }
+
+    if enum_options.error {
+        process_error(
+            parsed.variants.iter().zip(&options),
+            &parsed.ident,
+            &parsed.generics,
+        )
+        .to_tokens(&mut enum_def);
+    }
+
+    if let (Some(path), Some(target)) = (&enum_options.into, &enum_options.into_target) {
+        match into::process_into(
+            &conversions,
+            parsed.variants.iter(),
+            &parsed.ident,
+            &parsed.generics,
+            path,
+            target,
+        ) {
+            Ok(imp) => imp.to_tokens(&mut enum_def),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    if enum_options.export {
+        match flatten::process_export(&conversions, &parsed.ident, &parsed.generics) {
+            Ok(imp) => imp.to_tokens(&mut enum_def),
+            Err(e) => errors.push(e),
+        }
+    }
+
+    errors.to_tokens(&mut enum_def);
+
+    enum_def
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use quote::quote;
+    use syn::parse2;
+
+    #[test]
+    fn ace_it() {
+        let input = quote! {
+            enum Test {
+                A,
+                B(u32),
+                C { a: u32, b: u32 },
+            }
+        };
+        let expected = quote! {
+            enum Test {
+                A,
+                B(u32),
+                C { a: u32, b: u32 },
+            }
+
+            impl From<u32> for Test {
+                fn from(value: u32) -> Self {
+                    Self::B(value)
+                }
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn repeating_types_error() {
+        let input = quote! {
+            enum Test {
+                A,
+                B(u32),
+                C(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert!(result.to_string().contains("Duplicate variant type"));
+    }
+
+    #[test]
+    fn multiple_fields() {
+        let input = quote! {
+            enum Test {
+                A(u32, String),
+                B(),
+            }
+        };
+        let expected = quote! {
+            enum Test {
+                A(u32, String),
+                B(),
+            }
+
+            impl From<(u32, String)> for Test {
+                fn from((value0, value1): (u32, String)) -> Self {
+                    Self::A(value0, value1)
+                }
+            }
+            impl From<()> for Test {
+                fn from((): ()) -> Self {
+                    Self::B()
+                }
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn marked_field() {
+        let input = quote! {
+            enum Test {
+                A {
+                    #[source]
+                    a: u32,
+                    b: String,
+                },
+                B(String, #[from] u8),
+            }
+        };
+        let expected = quote! {
+            enum Test {
+                A {
+                    a: u32,
+                    b: String,
+                },
+                B(String, u8),
+            }
+
+            impl From<u32> for Test {
+                fn from(value: u32) -> Self {
+                    Self::A {
+                        a: value,
+                        b: <String as ::core::default::Default>::default()
+                    }
+                }
+            }
+            impl From<u8> for Test {
+                fn from(value: u8) -> Self {
+                    Self::B(<String as ::core::default::Default>::default(), value)
+                }
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn multiple_marked_fields_error() {
+        let input = quote! {
+            enum Test {
+                A(#[from] u32, #[source] u8),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert!(result.to_string().contains("Only one field"));
+    }
+
+    #[test]
+    fn repeating_tuple_error() {
+        let input = quote! {
+            enum Test {
+                A(u32, u32),
+                B((u32, u32)),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert!(result.to_string().contains("Duplicate variant type"));
+    }
+
+    #[test]
+    fn generic_enum() {
+        let input = quote! {
+            enum Test<'a, T: Clone, const N: usize> where T: Default {
+                A(&'a str),
+                B(Vec<T>),
+                C([u8; N]),
+            }
+        };
+        let expected = quote! {
+            enum Test<'a, T: Clone, const N: usize> where T: Default {
+                A(&'a str),
+                B(Vec<T>),
+                C([u8; N]),
+            }
+
+            impl<'a, T: Clone, const N: usize> From<&'a str> for Test<'a, T, N> where T: Default {
+                fn from(value: &'a str) -> Self {
+                    Self::A(value)
+                }
+            }
+            impl<'a, T: Clone, const N: usize> From<Vec<T> > for Test<'a, T, N> where T: Default {
+                fn from(value: Vec<T>) -> Self {
+                    Self::B(value)
+                }
+            }
+            impl<'a, T: Clone, const N: usize> From<[u8; N]> for Test<'a, T, N> where T: Default {
+                fn from(value: [u8; N]) -> Self {
+                    Self::C(value)
+                }
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn type_param_variant_error() {
+        let input = quote! {
+            enum Test<T, U> {
+                A(T),
+                B(U),
+                C(Vec<T>),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains(
+            "Variant wraps the type parameter `T`, its From impl would conflict with the one from `U` of variant `B` when `T` is `U`"
+        ));
+        assert!(result.contains("Variant wraps the type parameter `U`"));
+    }
+
+    #[test]
+    fn type_param_variant_without_overlap() {
+        let input = quote! {
+            enum Test<T> {
+                A(T),
+                B(Vec<T>),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(try_from)).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains(&quote!(impl<T> From<T> for Test<T>).to_string()));
+        assert!(result.contains("impl < T > From < Vec < T > > for Test < T >"));
+        assert!(!result.contains("compile_error"));
+        assert!(!result.contains("for T {"));
+        assert!(!result.contains("for & '__ace_it T {"));
+    }
+
+    #[test]
+    fn try_from_skips_uncovered_type_params() {
+        let input = quote! {
+            enum Test<'a, T> {
+                A(Box<T>),
+                B(&'a T),
+                C(Box<Vec<T>>),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(try_from)).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains("impl < 'a , T > From < Box < T > > for Test < 'a , T >"));
+        assert!(!result.contains("for Box < T > {"));
+        assert!(!result.contains("for & 'a T {"));
+        assert!(result.contains("for Box < Vec < T > > {"));
+    }
+
+    #[test]
+    fn skipped_type_param_variant() {
+        let input = quote! {
+            enum Test<T> {
+                A(u32),
+                #[ace_it(skip)]
+                B(T),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(!result.contains("compile_error"));
+    }
+
+    #[test]
+    fn skipped_variant() {
+        let input = quote! {
+            enum Test {
+                A(u32),
+                #[ace_it(skip)]
+                B(u32),
+            }
+        };
+        let expected = quote! {
+            enum Test {
+                A(u32),
+                B(u32),
+            }
+
+            impl From<u32> for Test {
+                fn from(value: u32) -> Self {
+                    Self::A(value)
+                }
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn unknown_variant_option() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(skipp)]
+                A(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert!(result.to_string().contains("Unknown variant option"));
+    }
+
+    #[test]
+    fn try_from() {
+        let input = quote! {
+            enum Test<T> {
+                A(u32),
+                B { #[from] b: Vec<T>, c: u8 },
+                C(u8, u16),
+            }
+        };
+        let expected = quote! {
+            impl<T> ::core::convert::TryFrom<Test<T> > for u32 {
+                type Error = Test<T>;
+
+                fn try_from(value: Test<T>) -> ::core::result::Result<Self, Self::Error> {
+                    match value {
+                        Test::A(value) => ::core::result::Result::Ok(value),
+                        #[allow(unreachable_patterns)]
+                        other => ::core::result::Result::Err(other),
+                    }
+                }
+            }
+            impl<'__ace_it, T> ::core::convert::TryFrom<&'__ace_it Test<T> > for &'__ace_it u32 {
+                type Error = &'__ace_it Test<T>;
+
+                fn try_from(value: &'__ace_it Test<T>) -> ::core::result::Result<Self, Self::Error> {
+                    match value {
+                        Test::A(value) => ::core::result::Result::Ok(value),
+                        #[allow(unreachable_patterns)]
+                        other => ::core::result::Result::Err(other),
+                    }
+                }
+            }
+        };
+        let options: EnumOptions = parse2(quote!(try_from)).unwrap();
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains(&expected.to_string()));
+        assert!(result.contains(
+            &quote!(Test::B { b: value, .. } => ::core::result::Result::Ok(value)).to_string()
+        ));
+        assert!(result.contains(
+            &quote!(Test::C(value0, value1) => ::core::result::Result::Ok((value0, value1)))
+                .to_string()
+        ));
+        assert!(!result.contains(&quote!(for &'__ace_it (u8, u16)).to_string()));
+    }
+
+    #[test]
+    fn unknown_enum_option() {
+        assert!(parse2::<EnumOptions>(quote!(try_from, tryfrom)).is_err());
+    }
+
+    #[test]
+    fn error() {
+        let input = quote! {
+            enum Test {
+                A,
+                B(std::io::Error),
+                C { #[source] c: std::fmt::Error, d: u8 },
+                #[ace_it(skip)]
+                D(u8, u16),
+            }
+        };
+        let options: EnumOptions = parse2(quote!(error)).unwrap();
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        let arms = quote! {
+            match self {
+                Self::A { .. } => ::core::option::Option::None,
+                Self::B { 0: source, .. } => (&__AceItSource(source)).__ace_it_source(),
+                Self::C { c: source, .. } => (&__AceItSource(source)).__ace_it_source(),
+                Self::D { .. } => ::core::option::Option::None,
+            }
+        };
+        assert!(result.contains(&quote!(impl ::std::error::Error for Test).to_string()));
+        assert!(result.contains(&arms.to_string()));
+    }
+
+    #[test]
+    fn display_without_enum_option_error() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(display = "{0}")]
+                A(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert!(result.to_string().contains("require `#[ace_it(display)]`"));
+    }
+
+    #[test]
+    fn accessors() {
+        let input = quote! {
+            pub enum Test {
+                ParseInt(u32),
+                B { b: u8 },
+            }
+        };
+        let expected = quote! {
+            #[doc = "Returns a reference to the value of [`Self::ParseInt`], if it is one."]
+            #[inline]
+            pub fn as_parse_int(&self) -> ::core::option::Option<&u32> {
+                match self {
+                    Self::ParseInt(value0) => ::core::option::Option::Some(value0),
+                    #[allow(unreachable_patterns)]
+                    _ => ::core::option::Option::None,
+                }
+            }
+        };
+        let options: EnumOptions = parse2(quote!(accessors)).unwrap();
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains(&expected.to_string()));
+        assert!(result.contains("is_b"));
+        assert!(!result.contains("as_b"));
+    }
+
+    #[test]
+    fn repeating_std_path_error() {
+        let input = quote! {
+            enum Test {
+                A(::std::vec::Vec<u8>),
+                B(alloc::vec::Vec<u8>),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert!(result.to_string().contains("Duplicate variant type"));
+    }
+
+    #[test]
+    fn ambiguous_type_warning() {
+        let input = quote! {
+            enum Test {
+                A(io::Error),
+                B(fmt::Error),
+                C(Error),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains("`Error` might be the same type as `io::Error` of variant `A`"));
+        assert!(!result.contains("`fmt::Error` might be the same type"));
+    }
+
+    #[test]
+    fn allowed_ambiguous_type_warning() {
+        let input = quote! {
+            #[allow(deprecated)]
+            enum Test {
+                A(io::Error),
+                #[allow(dead_code)]
+                B(Error),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains(
+            "# [allow (deprecated)] # [allow (dead_code)] const _ : () = { # [deprecated"
+        ));
+    }
+
+    #[test]
+    fn all_duplicates_reported() {
+        let input = quote! {
+            enum Test {
+                A(u32),
+                B(u32),
+                C(String),
+                D(u32),
+                E(String),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert_eq!(
+            result.matches("is already wrapped by variant `A`").count(),
+            2
+        );
+        assert_eq!(
+            result.matches("is already wrapped by variant `C`").count(),
+            1
+        );
+        assert_eq!(
+            result
+                .matches("note: variant `A` wraps `u32` first")
+                .count(),
+            2
+        );
+        // The enum and the From impls of the first variants are still generated
+        assert!(result.starts_with(&quote!(enum Test).to_string()));
+        assert_eq!(result.matches("impl From").count(), 2);
+    }
+
+    #[test]
+    fn primary_variant() {
+        let input = quote! {
+            enum Test {
+                A(u32),
+                #[ace_it(primary)]
+                B(u32),
+            }
+        };
+        let expected = quote! {
+            enum Test {
+                A(u32),
+                B(u32),
+            }
+
+            impl From<u32> for Test {
+                fn from(value: u32) -> Self {
+                    Self::B(value)
+                }
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed);
+        assert_eq!(result.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn multiple_primary_variants_error() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(primary)]
+                A(u32),
+                #[ace_it(primary)]
+                B(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(
+            result.contains("Only one of the variants wrapping `u32` can be `#[ace_it(primary)]`")
+        );
+        assert!(result.contains("Self :: A (value)"));
+    }
+
+    #[test]
+    fn boxed_variant() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(boxed)]
+                A(Box<u32>),
+                #[ace_it(boxed)]
+                B { #[from] b: std::sync::Arc<String>, c: u8 },
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        let a = quote! {
+            impl From<u32> for Test {
+                fn from(value: u32) -> Self {
+                    let value = ::core::convert::Into::into(value);
+                    Self::A(value)
+                }
+            }
+        };
+        assert!(result.contains(&a.to_string()));
+        assert!(result.contains(&quote!(impl From<Box<u32> > for Test).to_string()));
+        assert!(result.contains(&quote!(impl From<String> for Test).to_string()));
+    }
+
+    #[test]
+    fn boxed_enum() {
+        let input = quote! {
+            enum Test {
+                A(u32),
+                B(Box<String>),
+                C(u8, u16),
+            }
+        };
+        let expected = quote! {
+            enum Test {
+                A(::std::boxed::Box<u32>),
+                B(Box<String>),
+                C(u8, u16),
+            }
+        };
+        let options: EnumOptions = parse2(quote!(boxed)).unwrap();
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.starts_with(&expected.to_string()));
+        assert!(result.contains(&quote!(impl From<u32> for Test).to_string()));
+        assert!(result.contains(&quote!(impl From<String> for Test).to_string()));
+        assert!(!result.contains("compile_error"));
+    }
+
+    #[test]
+    fn boxed_enum_keeps_unsized_pointers() {
+        let input = quote! {
+            enum Test {
+                A(u32),
+                Other(Box<dyn std::error::Error + Send + Sync>),
+                Text(Box<str>),
+            }
+        };
+        let expected = quote! {
+            enum Test {
+                A(::std::boxed::Box<u32>),
+                Other(Box<dyn std::error::Error + Send + Sync>),
+                Text(Box<str>),
+            }
+        };
+        let options: EnumOptions = parse2(quote!(boxed, error)).unwrap();
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.starts_with(&expected.to_string()));
+        assert!(result.contains(
+            &quote!(impl From<Box<dyn std::error::Error + Send + Sync> > for Test).to_string()
+        ));
+        assert!(result.contains(
+            "Self :: Other { 0 : source , .. } => :: core :: option :: Option :: Some (& * * source)"
+        ));
+        assert!(!result.contains("compile_error"));
+    }
+
+    #[test]
+    fn boxed_without_pointer_error() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(boxed)]
+                A(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains("needs the variant to wrap a Box, Arc or Rc"));
+    }
+
+    #[test]
+    fn additional_sources() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(from(&str, Cow<'_, str>))]
+                A(String),
+                #[ace_it(from(&'static str))]
+                B(Vec<u8>),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        let a = quote! {
+            impl From<&str> for Test {
+                fn from(value: &str) -> Self {
+                    let value = ::core::convert::Into::into(value);
+                    Self::A(value)
+                }
+            }
+        };
+        assert!(result.contains(&a.to_string()));
+        assert!(result.contains(&quote!(impl From<Cow<'_, str> > for Test).to_string()));
+        assert!(result.contains("`&'static str` is already wrapped by variant `A`"));
+    }
+
+    #[test]
+    fn conversion_function() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(with = convert, from = u8)]
+                A(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        let from = quote! {
+            impl From<u8> for Test {
+                fn from(value: u8) -> Self {
+                    <_ as __AceItWith<Self>>::__ace_it_with((convert)(value))
+                }
+            }
+        };
+        let wrap = quote! {
+            impl __AceItWith<Test> for u32 {
+                fn __ace_it_with(self) -> Test {
+                    let value = self;
+                    Test::A(value)
+                }
+            }
+        };
+        assert!(result.contains(&from.to_string()));
+        assert!(result.contains(&wrap.to_string()));
+        assert!(!result.contains(&quote!(impl From<u32> for Test).to_string()));
+    }
+
+    #[test]
+    fn conversion_function_without_source_error() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(with = convert)]
+                A(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains("needs the type it converts from"));
+    }
+
+    #[test]
+    fn unit_variant_source() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(from = std::fmt::Error)]
+                Fmt,
+                Other,
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        let expected = quote! {
+            impl From<std::fmt::Error> for Test {
+                fn from(_: std::fmt::Error) -> Self {
+                    Self::Fmt
+                }
+            }
+        };
+        assert!(result.contains(&expected.to_string()));
+        assert_eq!(result.matches("impl From").count(), 1);
+    }
+
+    #[test]
+    fn unit_variant_duplicate_error() {
+        let input = quote! {
+            enum Test {
+                Fmt(core::fmt::Error),
+                #[ace_it(from = std::fmt::Error)]
+                Formatting,
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains("is already wrapped by variant `Fmt`"));
+        assert_eq!(result.matches("impl From").count(), 1);
+    }
+
+    #[test]
+    fn exported_sources() {
+        let input = quote! {
+            enum Test {
+                A(u8),
+                #[ace_it(from = u32)]
+                B(u64),
+                #[ace_it(skip)]
+                C(String),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(export)).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        let expected = quote!(
+            ::ace_it::__ace_it_flatten! { $mode { $($args)* } { $($item)* } [u8, u64, u32] }
+        );
+        assert!(result.contains(&expected.to_string()));
+        assert!(result.contains(
+            &quote!(
+                pub(crate) use __ace_it_Test as Test;
+            )
+            .to_string()
+        ));
+    }
+
+    #[test]
+    fn exported_and_flattened_cfgs() {
+        let input = quote! {
+            enum Test {
+                A(u8),
+                #[cfg(feature = "tls")]
+                B(TlsError),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(export)).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        let sources = quote!([
+            u8,
+            #[cfg(feature = "tls")]
+            TlsError
+        ]);
+        let variants =
+            quote!({ #[cfg(feature = "tls")] TlsError } { __AceItTarget::B(value) } { value });
+        assert!(result.contains(&sources.to_string()));
+        assert!(result.contains(&variants.to_string()));
+
+        let input = quote! {
+            attribute
+            {}
+            {
+                enum Outer {
+                    #[ace_it(flatten)]
+                    Inner(Inner),
+                }
+            }
+            [u8, #[cfg(feature = "tls")] TlsError]
+        };
+        let flattened: flatten::Flattened = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), flattened.item).to_string();
+        let from = quote! {
+            #[cfg(feature = "tls")]
+            impl From<TlsError> for Outer
+        };
+        assert!(result.contains(&from.to_string()));
+
+        let input = quote! {
+            enum Narrow {
+                A(u8),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let mut options: EnumOptions = parse2(quote!(into = Wide)).unwrap();
+        options.into_target = Some(
+            parse2(quote! {
+                [u8]
+                [{ #[cfg(unix)] u8 } { __AceItTarget::A(value) } { value }]
+            })
+            .unwrap(),
+        );
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains(
+            &quote!(#[cfg(unix)] __AceItTarget::A(value) => ::core::result::Result::Ok).to_string()
+        ));
+    }
+
+    #[test]
+    fn into_superset() {
+        let input = quote! {
+            enum Test {
+                A(u8),
+                B { #[from] value: u16, name: String },
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let mut options: EnumOptions = parse2(quote!(into = Target)).unwrap();
+        options.into_target = Some(
+            parse2(quote! {
+                [u8, u16, u32]
+                [{ u8 } { __AceItTarget::X(value) } { value }, { u32 } { __AceItTarget::Y(value) } { value }]
+            })
+            .unwrap(),
+        );
+        let result = ace_it_impl(options, parsed).to_string();
+        let from = quote! {
+            match value {
+                Test::A(value) => <Self as ::core::convert::From<u8>>::from(value),
+                Test::B { value: value, .. } => <Self as ::core::convert::From<u16>>::from(value),
+            }
+        };
+        let try_from = quote! {
+            match value {
+                __AceItTarget::X(value) => ::core::result::Result::Ok(<Self as ::core::convert::From<u8>>::from(value)),
+                #[allow(unreachable_patterns)]
+                other => ::core::result::Result::Err(other),
+            }
+        };
+        assert!(result.contains(&quote!(impl ::core::convert::From<Test> for Target).to_string()));
+        assert!(result.contains(&from.to_string()));
+        assert!(result.contains(&try_from.to_string()));
+    }
+
+    #[test]
+    fn into_superset_missing_types_error() {
+        let input = quote! {
+            enum Test {
+                A(u8),
+                B(String),
+                C(Vec<u8>),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let mut options: EnumOptions = parse2(quote!(into = Target)).unwrap();
+        options.into_target = Some(parse2(quote!([u8] [])).unwrap());
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains("`Target` isn't converted from `String`, `Vec<u8>`"));
+        assert!(!result.contains(&quote!(for Target).to_string()));
+    }
+
+    #[test]
+    fn derive_emits_impls_only() {
+        let input = quote! {
+            enum Test {
+                A(u8),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = expand(Mode::Derive, quote!(boxed), parsed).to_string();
+        assert!(!result.contains(&quote!(enum Test).to_string()));
+        assert!(result.contains(&quote!(impl From<u8> for Test).to_string()));
+        assert!(result.contains("can't be set on the enum with `#[derive(AceIt)]`"));
+    }
+
+    #[test]
+    fn chain_emits_enum_first() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(flatten)]
+                Inner(Inner),
+                #[ace_it(skip)]
+                A(u8),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = expand(Mode::Attribute, quote!(boxed), parsed).to_string();
+        let emitted = quote! {
+            enum Test {
+                Inner(::std::boxed::Box<Inner>),
+                A(u8),
+            }
+        };
+        assert!(result.starts_with(&emitted.to_string()));
+        assert!(result.contains("Inner ! { flatten emitted { boxed }"));
+    }
+
+    #[test]
+    fn forwarded_variant_attributes() {
+        let input = quote! {
+            enum Test {
+                #[cfg(feature = "a")]
+                #[cfg_attr(test, cfg(unix), doc = "A")]
+                A(u8),
+                #[deprecated]
+                B(u16),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        let a = quote! {
+            #[cfg(feature = "a")]
+            #[cfg_attr(test, cfg(unix))]
+            impl From<u8> for Test
+        };
+        let b = quote! {
+            #[allow(deprecated)]
+            impl From<u16> for Test
+        };
+        assert!(result.contains(&a.to_string()));
+        assert!(result.contains(&b.to_string()));
+    }
+
+    #[test]
+    fn membership_traits() {
+        let input = quote! {
+            enum Test {
+                A(u8),
+                B(u32, u64),
+                #[ace_it(from = u16)]
+                C(u32),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(membership)).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        let project = quote! {
+            fn project(self) -> ::core::result::Result<u8, Self> {
+                match self {
+                    Self::A(value) => ::core::result::Result::Ok(value),
+                    #[allow(unreachable_patterns)]
+                    other => ::core::result::Result::Err(other),
+                }
+            }
+        };
+        assert!(result.contains(&project.to_string()));
+        for ty in ["u8", "(u32 , u64)", "u32", "u16"] {
+            assert!(result.contains(&format!("impl :: ace_it :: Inject < {} > for Test", ty)));
+        }
+        assert_eq!(result.matches("impl :: ace_it :: Project").count(), 2);
+    }
+
+    #[test]
+    fn delegated_trait() {
+        let input = quote! {
+            enum Test<T> {
+                #[ace_it(skip)]
+                A(T),
+                B { #[from] inner: std::vec::IntoIter<u8>, len: usize },
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(delegate(Iterator))).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        let head = quote! {
+            impl<T> ::core::iter::Iterator for Test<T>
+            where
+                T: ::core::iter::Iterator,
+                std::vec::IntoIter<u8>: ::core::iter::Iterator<Item = <T as ::core::iter::Iterator>::Item>
+        };
+        let item = quote!(
+            type Item = <T as ::core::iter::Iterator>::Item;
+        );
+        let next = quote! {
+            match self {
+                Self::A { 0: __ace_it_inner, .. } => <T as ::core::iter::Iterator>::next(__ace_it_inner,),
+                Self::B { inner: __ace_it_inner, .. } => <std::vec::IntoIter<u8> as ::core::iter::Iterator>::next(__ace_it_inner,),
+            }
+        };
+        assert!(result.contains(&head.to_string()));
+        assert!(result.contains(&item.to_string()));
+        assert!(result.contains(&next.to_string()));
+    }
+
+    #[test]
+    fn delegated_trait_without_value_error() {
+        let input = quote! {
+            enum Test {
+                A(std::io::Empty),
+                B,
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(delegate(Read, Handler))).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains("Variant `B` doesn't wrap a single value to delegate `Read` to"));
+        assert!(result.contains("Can only delegate well-known std traits"));
+    }
+
+    #[test]
+    fn boxed_dyn_error_source() {
+        let input = quote! {
+            enum Test {
+                A(Box<dyn std::error::Error + Send + Sync>),
+                B(Box<dyn std::error::Error + 'static>),
+                C(Box<dyn Error + 'a>),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(error)).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        for variant in ["A", "B"] {
+            let arm = format!(
+                "Self :: {} {{ 0 : source , .. }} => :: core :: option :: Option :: Some (& * * source)",
+                variant
+            );
+            assert!(result.contains(&arm));
+        }
+        assert!(result.contains("Self :: C { .. } => :: core :: option :: Option :: None"));
+    }
+
+    #[test]
+    fn lifetime_error_source() {
+        let input = quote! {
+            enum Test<'a> {
+                Parse(ParseError<'a>),
+                Text(&'static str),
+                Io(std::io::Error),
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let options: EnumOptions = parse2(quote!(error)).unwrap();
+        let result = ace_it_impl(options, parsed).to_string();
+        assert!(result.contains("Self :: Parse { .. } => :: core :: option :: Option :: None"));
+        for variant in ["Text", "Io"] {
+            let arm = format!(
+                "Self :: {} {{ 0 : source , .. }} => (& __AceItSource (source)) . __ace_it_source ()",
+                variant
+            );
+            assert!(result.contains(&arm));
+        }
+    }
+
+    #[test]
+    fn named_variant_without_marked_field_errors() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(from(&str))]
+                A { text: String },
+                #[ace_it(primary)]
+                B { code: u8 },
+                C { ignored: u16 },
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains(
+            "`#[ace_it(from(...))]` needs a field marked with `#[from]` on a variant with named fields"
+        ));
+        assert!(result.contains(
+            "`#[ace_it(primary)]` needs a field marked with `#[from]` on a variant with named fields"
+        ));
+        assert!(!result.contains("impl From"));
+    }
+
+    #[test]
+    fn named_variant_conversion_function() {
+        let input = quote! {
+            enum Test {
+                #[ace_it(with = convert, from = u8)]
+                A { code: u8, retry: bool },
+            }
+        };
+        let parsed: syn::ItemEnum = parse2(input).unwrap();
+        let expected = quote! {
+            impl From<u8> for Test {
+                fn from(value: u8) -> Self {
+                    (convert)(value)
+                }
+            }
+        };
+        let result = ace_it_impl(EnumOptions::default(), parsed).to_string();
+        assert!(result.contains(&expected.to_string()));
+        assert!(!result.contains("__AceItWith"));
+    }
+}
@@ -0,0 +1,183 @@
+//! Conversions of structs that wrap a single value.
+
+use proc_macro2::TokenStream;
+use quote::{quote, ToTokens};
+use syn::{
+    parse::{Parse, ParseStream},
+    ItemStruct, Member,
+};
+
+use crate::{parse_options, uncovers_type_param, Errors};
+
+/// Options that can be set on the struct with `#[ace_it(...)]`.
+#[derive(Default)]
+pub(crate) struct StructOptions {
+    /// Generate a From impl taking the wrapped value back out of the struct.
+    into_inner: bool,
+    /// Generate a [std::ops::Deref] impl to the wrapped value.
+    deref: bool,
+    /// Generate an [AsRef] impl to the wrapped value.
+    as_ref: bool,
+}
+
+impl Parse for StructOptions {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+        parse_options(input, "struct", |option, _| {
+            match option.to_string().as_str() {
+                "into_inner" => options.into_inner = true,
+                "deref" => options.deref = true,
+                "as_ref" => options.as_ref = true,
+                _ => return Ok(false),
+            }
+            Ok(true)
+        })?;
+        Ok(options)
+    }
+}
+
+/// Expands the struct, `derive` is true for `#[derive(AceIt)]`, which only emits the impls.
+pub(crate) fn expand(derive: bool, args: TokenStream, parsed: ItemStruct) -> TokenStream {
+    let mut tokens = if derive {
+        TokenStream::new()
+    } else {
+        parsed.to_token_stream()
+    };
+
+    let imp = syn::parse2(args).and_then(|options| process_struct(&options, &parsed));
+    match imp {
+        Ok(imp) => imp.to_tokens(&mut tokens),
+        Err(e) => e.to_compile_error().to_tokens(&mut tokens),
+    }
+    tokens
+}
+
+/// Generates the From impl of the struct, and the ones set with the options.
+///
+/// Options that can't be generated are reported along with the other impls.
+fn process_struct(options: &StructOptions, parsed: &ItemStruct) -> syn::Result<TokenStream> {
+    if parsed.fields.len() != 1 {
+        return Err(syn::Error::new(
+            parsed.ident.span(),
+            "`ace_it` needs the struct to wrap a single value",
+        ));
+    }
+
+    let field = parsed.fields.iter().next().unwrap();
+    let ty = &field.ty;
+    let member = match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(0.into()),
+    };
+    let struct_name = &parsed.ident;
+    let (impl_generics, ty_generics, where_clause) = parsed.generics.split_for_impl();
+
+    let mut errors = Errors::default();
+    let mut impls = quote! {
+        impl #impl_generics From<#ty> for #struct_name #ty_generics #where_clause {
+            fn from(value: #ty) -> Self {
+                Self { #member: value }
+            }
+        }
+    };
+
+    // Implementing a foreign trait for a type parameter isn't allowed, even with a local type in it.
+    if options.into_inner && uncovers_type_param(ty, &parsed.generics) {
+        errors.push(syn::Error::new_spanned(
+            ty,
+            "`#[ace_it(into_inner)]` can't convert into a type parameter, get the value out with the field instead",
+        ));
+    } else if options.into_inner {
+        impls.extend(quote! {
+            impl #impl_generics From<#struct_name #ty_generics> for #ty #where_clause {
+                fn from(value: #struct_name #ty_generics) -> Self {
+                    value.#member
+                }
+            }
+        });
+    }
+
+    if options.deref {
+        impls.extend(quote! {
+            impl #impl_generics ::core::ops::Deref for #struct_name #ty_generics #where_clause {
+                type Target = #ty;
+
+                fn deref(&self) -> &Self::Target {
+                    &self.#member
+                }
+            }
+        });
+    }
+
+    if options.as_ref {
+        impls.extend(quote! {
+            impl #impl_generics ::core::convert::AsRef<#ty> for #struct_name #ty_generics #where_clause {
+                fn as_ref(&self) -> &#ty {
+                    &self.#member
+                }
+            }
+        });
+    }
+
+    errors.to_tokens(&mut impls);
+    Ok(impls)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use syn::parse2;
+
+    #[test]
+    fn named_field() {
+        let input = quote! {
+            struct Wrapper {
+                inner: String,
+            }
+        };
+        let parsed: ItemStruct = parse2(input).unwrap();
+        let result = expand(true, quote!(into_inner, as_ref), parsed).to_string();
+        let expected = quote! {
+            impl From<String> for Wrapper {
+                fn from(value: String) -> Self {
+                    Self { inner: value }
+                }
+            }
+            impl From<Wrapper> for String {
+                fn from(value: Wrapper) -> Self {
+                    value.inner
+                }
+            }
+            impl ::core::convert::AsRef<String> for Wrapper {
+                fn as_ref(&self) -> &String {
+                    &self.inner
+                }
+            }
+        };
+        assert_eq!(result, expected.to_string());
+    }
+
+    #[test]
+    fn multiple_fields_error() {
+        let input = quote! {
+            struct Pair(u8, u8);
+        };
+        let parsed: ItemStruct = parse2(input.clone()).unwrap();
+        let result = expand(false, TokenStream::new(), parsed).to_string();
+        assert!(result.contains("needs the struct to wrap a single value"));
+        assert!(result.contains(&input.to_string()));
+    }
+
+    #[test]
+    fn into_inner_type_param_error() {
+        let input = quote! {
+            struct Wrapper<T>(Box<T>);
+        };
+        let parsed: ItemStruct = parse2(input).unwrap();
+        let result = expand(true, quote!(into_inner, deref), parsed).to_string();
+        assert!(result.contains("can't convert into a type parameter"));
+        assert!(result.contains(&quote!(impl<T> From<Box<T> > for Wrapper<T>).to_string()));
+        assert!(result.contains(&quote!(impl<T> ::core::ops::Deref for Wrapper<T>).to_string()));
+        assert!(!result.contains(&quote!(for Box<T>).to_string()));
+    }
+}
@@ -0,0 +1,224 @@
+//! Normalization of types, so that different ways of writing the same type compare equal.
+//!
+//! Only what can be resolved without seeing the `use` items is normalized:
+//! `core`/`alloc` paths, the std prelude, `Self`, parentheses and formatting.
+//! Lifetimes are removed, as impls for types that only differ in lifetimes conflict anyway.
+
+use quote::ToTokens;
+use syn::{
+    visit_mut::{self, VisitMut},
+    AngleBracketedGenericArguments, GenericArgument, Path, PathArguments, Type, TypeReference,
+};
+
+/// Paths of the std prelude types, which are normalized to their bare names.
+const PRELUDE: &[(&str, &str)] = &[
+    ("boxed", "Box"),
+    ("option", "Option"),
+    ("result", "Result"),
+    ("string", "String"),
+    ("vec", "Vec"),
+];
+
+/// Returns the type with paths normalized and `Self` replaced with `self_ty`.
+pub(crate) fn normalize_type(ty: &Type, self_ty: &Type) -> Type {
+    let mut ty = ty.clone();
+    Normalize { self_ty }.visit_type_mut(&mut ty);
+    ty
+}
+
+/// Returns a string that's the same for types that were normalized to the same type.
+pub(crate) fn type_key(ty: &Type) -> String {
+    ty.to_token_stream().to_string()
+}
+
+/// Returns the type as it would be written in code, for use in messages.
+pub(crate) fn type_name(ty: &Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .replace(" :: ", "::")
+        .replace(":: ", "::")
+        .replace(" < ", "<")
+        .replace(" >", ">")
+        .replace(" ,", ",")
+        .replace("& ", "&")
+}
+
+/// Returns true if two normalized types, that aren't the same, might still be the same type after name resolution.
+///
+/// That's the case when one of the paths is a suffix of the other one, e.g. `Error` and `io::Error`.
+pub(crate) fn may_be_same(a: &Type, b: &Type) -> bool {
+    let (Type::Path(a), Type::Path(b)) = (a, b) else {
+        return false;
+    };
+    if a.qself.is_some() || b.qself.is_some() {
+        return false;
+    }
+
+    let segments = |path: &Path| -> Vec<String> {
+        path.segments
+            .iter()
+            .map(|segment| segment.to_token_stream().to_string())
+            .collect()
+    };
+    let (a, b) = (segments(&a.path), segments(&b.path));
+    let (shorter, longer) = if a.len() < b.len() { (a, b) } else { (b, a) };
+    shorter.len() < longer.len() && longer.ends_with(&shorter)
+}
+
+struct Normalize<'a> {
+    self_ty: &'a Type,
+}
+
+impl VisitMut for Normalize<'_> {
+    fn visit_type_mut(&mut self, ty: &mut Type) {
+        match ty {
+            Type::Paren(paren) => *ty = (*paren.elem).clone(),
+            Type::Group(group) => *ty = (*group.elem).clone(),
+            Type::Path(path) if path.qself.is_none() && path.path.is_ident("Self") => {
+                *ty = self.self_ty.clone();
+                return;
+            }
+            _ => {}
+        }
+        visit_mut::visit_type_mut(self, ty);
+    }
+
+    fn visit_type_reference_mut(&mut self, reference: &mut TypeReference) {
+        reference.lifetime = None;
+        visit_mut::visit_type_reference_mut(self, reference);
+    }
+
+    fn visit_angle_bracketed_generic_arguments_mut(
+        &mut self,
+        arguments: &mut AngleBracketedGenericArguments,
+    ) {
+        arguments.args = std::mem::take(&mut arguments.args)
+            .into_iter()
+            .filter(|argument| !matches!(argument, GenericArgument::Lifetime(_)))
+            .collect();
+        visit_mut::visit_angle_bracketed_generic_arguments_mut(self, arguments);
+    }
+
+    fn visit_path_mut(&mut self, path: &mut Path) {
+        visit_mut::visit_path_mut(self, path);
+
+        path.leading_colon = None;
+        let Some(first) = path.segments.first_mut() else {
+            return;
+        };
+        if first.ident != "std" && first.ident != "core" && first.ident != "alloc" {
+            return;
+        }
+        if path.segments.len() < 3 || !path.segments.iter().take(2).all(|s| s.arguments.is_empty())
+        {
+            return;
+        }
+
+        let module = path.segments[1].ident.to_string();
+        let name = path.segments[2].ident.to_string();
+        let is_prelude =
+            path.segments.len() == 3 && PRELUDE.contains(&(module.as_str(), name.as_str()));
+        let is_primitive = path.segments.len() == 3
+            && module == "primitive"
+            && matches!(path.segments[2].arguments, PathArguments::None);
+        let skip = if is_prelude || is_primitive { 2 } else { 1 };
+
+        path.segments = std::mem::take(&mut path.segments)
+            .into_iter()
+            .skip(skip)
+            .collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use syn::parse_quote;
+
+    fn normalized(ty: Type) -> String {
+        type_key(&normalize_type(&ty, &parse_quote!(Test<T>)))
+    }
+
+    #[test]
+    fn std_paths() {
+        assert_eq!(
+            normalized(parse_quote!(std::io::Error)),
+            normalized(parse_quote!(io::Error))
+        );
+        assert_eq!(
+            normalized(parse_quote!(::core::fmt::Error)),
+            normalized(parse_quote!(fmt::Error))
+        );
+        assert_eq!(
+            normalized(parse_quote!(alloc::vec::Vec<u8>)),
+            normalized(parse_quote!(Vec<u8>))
+        );
+        assert_eq!(
+            normalized(parse_quote!(std::boxed::Box<std::string::String>)),
+            normalized(parse_quote!(Box<String>))
+        );
+        assert_eq!(
+            normalized(parse_quote!(core::primitive::u8)),
+            normalized(parse_quote!(u8))
+        );
+        assert_ne!(
+            normalized(parse_quote!(io::Error)),
+            normalized(parse_quote!(fmt::Error))
+        );
+    }
+
+    #[test]
+    fn self_and_parens() {
+        assert_eq!(
+            normalized(parse_quote!(Box<Self>)),
+            normalized(parse_quote!(Box<Test<T>>))
+        );
+        assert_eq!(normalized(parse_quote!((u8))), normalized(parse_quote!(u8)));
+        assert_eq!(
+            normalized(parse_quote!(&'static str)),
+            normalized(parse_quote!(&str))
+        );
+        assert_eq!(
+            normalized(parse_quote!(Cow<'a, str>)),
+            normalized(parse_quote!(Cow<'_, str>))
+        );
+        assert_eq!(
+            normalized(parse_quote!(&'a (dyn Fn()))),
+            normalized(parse_quote!(&'a dyn Fn()))
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(
+            type_name(&parse_quote!(::std::io::Error)),
+            "::std::io::Error"
+        );
+        assert_eq!(
+            type_name(&parse_quote!(Vec<(u8, &'a str)>)),
+            "Vec<(u8, &'a str)>"
+        );
+    }
+
+    #[test]
+    fn maybe_same_types() {
+        let ty = |ty: Type| normalize_type(&ty, &parse_quote!(Test));
+        assert!(may_be_same(
+            &ty(parse_quote!(Error)),
+            &ty(parse_quote!(io::Error))
+        ));
+        assert!(may_be_same(
+            &ty(parse_quote!(crate::Error)),
+            &ty(parse_quote!(Error))
+        ));
+        assert!(!may_be_same(
+            &ty(parse_quote!(io::Error)),
+            &ty(parse_quote!(fmt::Error))
+        ));
+        assert!(!may_be_same(
+            &ty(parse_quote!(Error)),
+            &ty(parse_quote!(Error))
+        ));
+    }
+}
@@ -0,0 +1,20 @@
+//! Traits implemented by the enums of [ace_it](https://docs.rs/ace_it), which re-exports them.
+
+/// A type that a value of `T` can be wrapped into.
+///
+/// `#[ace_it(membership)]` implements it for every type an enum is converted from, the same way as [From].
+pub trait Inject<T> {
+    /// Wraps the value.
+    fn inject(value: T) -> Self;
+}
+
+/// A type that can hold a value of `T`, which can be taken back out of it.
+///
+/// `#[ace_it(membership)]` implements it for every type a variant of an enum wraps as a single value.
+pub trait Project<T>: Sized {
+    /// Returns the value if it's held, or `self` back otherwise.
+    fn project(self) -> Result<T, Self>;
+
+    /// Returns a reference to the value if it's held.
+    fn project_ref(&self) -> Option<&T>;
+}
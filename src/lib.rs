@@ -25,7 +25,7 @@
 //! Now you can use `?` on any of these types and get an Error back.
 //! ```
 //! # #[macro_use] extern crate ace_it;
-//! # 
+//! #
 //! # #[derive(Debug)]
 //! # #[ace_it]
 //! # enum Error {
@@ -33,7 +33,7 @@
 //! #   ParseInt(std::num::ParseIntError),
 //! #   ParseFloat(std::num::ParseFloatError),
 //! # }
-//! 
+//!
 //! use std::io::Read;
 //!
 //! fn read_int<R: Read>(reader: &mut R) -> Result<i32, Error> {
@@ -41,157 +41,36 @@
 //!     reader.read_to_string(&mut buf)?;
 //!     Ok(buf.parse()?)
 //! }
+//! ```
+//!
+//! ## Membership traits
+//! With `#[ace_it(membership)]`, the enum also implements [Inject]<T> for each type it's converted from,
+//! and [Project]<T> for each type a variant wraps, so generic code can work with any enum that wraps a type.
+//! It's opt-in, as [Project] moves the value out of the enum, which doesn't compile if the enum implements [Drop].
+//! ```
+//! # #[macro_use] extern crate ace_it;
+//! use ace_it::Project;
+//!
+//! #[derive(Debug)]
+//! #[ace_it(membership)]
+//! enum Error {
+//!     Io(std::io::Error),
+//!     ParseInt(std::num::ParseIntError),
+//! }
+//!
+//! fn should_retry<E: Project<std::io::Error>>(error: &E) -> bool {
+//!     error
+//!         .project_ref()
+//!         .is_some_and(|error| error.kind() == std::io::ErrorKind::Interrupted)
+//! }
+//!
+//! let interrupted = Error::from(std::io::Error::from(std::io::ErrorKind::Interrupted));
+//! assert!(should_retry(&interrupted));
+//! assert!(!should_retry(&Error::from("ace".parse::<i32>().unwrap_err())));
+//! ```
 
-use std::collections::HashSet;
-
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::{quote, ToTokens};
-use syn::{spanned::Spanned, Fields, Variant};
-
-/// Generates [From] impls for the given enum.
-/// ## Usage
-/// ### Applying the macro
-/// This will generate the From impls for each type and turns it into an accompanying variant.
-/// ```
-/// # #[macro_use] extern crate ace_it;
-/// #[ace_it]
-/// enum Error {
-///   Io(std::io::Error),
-///   ParseInt(std::num::ParseIntError),
-///   ParseFloat(std::num::ParseFloatError),
-/// }
-/// ```
-/// ### Compile errors
-/// This will error because there are two variants with the same type.
-/// There is no way to know which one to use. 
-/// ```compile_fail
-/// # #[macro_use] extern crate ace_it;
-/// #[ace_it]
-/// enum SomeEnum {
-///     A(i32),
-///     B(i32) // Duplicate i32, shouldn't compile
-/// }
-/// ```
-#[proc_macro_attribute]
-pub fn ace_it(
-    _: proc_macro::TokenStream,
-    input: proc_macro::TokenStream,
-) -> proc_macro::TokenStream {
-    let parsed = syn::parse(input);
-
-    let parsed = match parsed {
-        Ok(parsed) => parsed,
-        Err(e) => return e.to_compile_error().into(),
-    };
-
-    ace_it_impl(parsed).into()
-}
-
-/// Generates From impls for the given enum.
-fn process_variants<'a>(
-    variants: impl Iterator<Item = &'a Variant>,
-    enum_name: &Ident,
-) -> Vec<TokenStream> {
-    let mut from_impls = Vec::new();
-
-    for variant in variants {
-        let variant_name = &variant.ident;
-
-        if let Fields::Unnamed(fields) = &variant.fields {
-            let types = &fields.unnamed;
-            let imp = quote! {
-                impl From<#types> for #enum_name {
-                    fn from(value: #types) -> Self {
-                        Self::#variant_name(value)
-                    }
-                }
-            };
-
-            from_impls.push(imp);
-        }
-    }
-
-    from_impls
-}
-
-fn find_duplicate_variant_type<'a>(variants: impl Iterator<Item = &'a Variant>) -> Option<Span> {
-    let mut types_map = HashSet::new();
-    for variant in variants {
-        if let Fields::Unnamed(fields) = &variant.fields {
-            let types = fields.unnamed.to_token_stream().to_string();
-
-            if !types_map.insert(types) {
-                return Some(variant.span());
-            }
-        }
-    }
-    None
-}
-
-fn ace_it_impl(parsed: syn::ItemEnum) -> TokenStream {
-    let mut enum_def = parsed.to_token_stream();
-    if let Some(var) = find_duplicate_variant_type(parsed.variants.iter()) {
-        return syn::Error::new(
-            var,
-            "Duplicate variant type, can't auto-generate From impls",
-        )
-        .to_compile_error();
-    }
-
-    let for_impls = process_variants(parsed.variants.iter(), &parsed.ident);
-
-    for impls in for_impls {
-        impls.to_tokens(&mut enum_def);
-    }
-
-    enum_def
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    use quote::quote;
-    use syn::parse2;
-
-    #[test]
-    fn ace_it() {
-        let input = quote! {
-            enum Test {
-                A,
-                B(u32),
-                C { a: u32, b: u32 },
-            }
-        };
-        let expected = quote! {
-            enum Test {
-                A,
-                B(u32),
-                C { a: u32, b: u32 },
-            }
-
-            impl From<u32> for Test {
-                fn from(value: u32) -> Self {
-                    Self::B(value)
-                }
-            }
-        };
-        let parsed: syn::ItemEnum = parse2(input).unwrap();
-        let result = ace_it_impl(parsed);
-        assert_eq!(result.to_string(), expected.to_string());
-    }
+pub use ace_it_macros::{ace_enum, ace_it, ace_trait, auto_enum, AceIt};
+pub use ace_it_runtime::{Inject, Project};
 
-    #[test]
-    fn repeating_types_error() {
-        let input = quote! {
-            enum Test {
-                A,
-                B(u32),
-                C(u32),
-            }
-        };
-        let parsed: syn::ItemEnum = parse2(input).unwrap();
-        let result = ace_it_impl(parsed);
-        assert!(result.to_string().contains("Duplicate variant type"));
-    }
-}
+#[doc(hidden)]
+pub use ace_it_macros::{__ace_it_delegate, __ace_it_flatten, __ace_it_into};